@@ -0,0 +1,8 @@
+fn main() {
+    // The BC encoder used by image_dds includes C++ code that needs the C++ runtime.
+    if std::env::var_os("CARGO_FEATURE_ENCODER").is_some()
+        && std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux")
+    {
+        println!("cargo:rustc-link-lib=stdc++");
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    io::{Cursor, SeekFrom},
+    path::PathBuf,
+};
+
+use binrw::{BinReaderExt, BinResult, VecArgs};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    check_file_size, check_footer, footer_pos, LayerMipmaps, NutexbFile, NutexbFooter, ReadOptions,
+    WriteFileOptions, WriteMode, FOOTER_SIZE, LAYER_MIPMAPS_SIZE,
+};
+
+pub async fn read_footer<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    options: ReadOptions,
+) -> BinResult<(u64, NutexbFooter)> {
+    let file_size = reader.seek(SeekFrom::End(0)).await?;
+    let footer_pos = footer_pos(file_size)?;
+    reader.seek(SeekFrom::Start(footer_pos)).await?;
+
+    // Parse the sections from memory since binrw only supports synchronous readers.
+    let mut bytes = [0u8; FOOTER_SIZE];
+    reader.read_exact(&mut bytes).await?;
+    let footer: NutexbFooter = Cursor::new(bytes).read_le()?;
+
+    let data_size = check_footer(&footer, file_size, options)?;
+    Ok((data_size, footer))
+}
+
+pub async fn read<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    options: ReadOptions,
+) -> BinResult<NutexbFile> {
+    let (data_size, footer) = read_footer(reader, options).await?;
+
+    // The size of the layer mipmaps is limited by the checks for the footer.
+    reader.seek(SeekFrom::Start(data_size)).await?;
+    let mut bytes = vec![0u8; LAYER_MIPMAPS_SIZE * footer.layer_count as usize];
+    reader.read_exact(&mut bytes).await?;
+    let layer_mipmaps: Vec<LayerMipmaps> = Cursor::new(bytes).read_le_args(VecArgs {
+        count: footer.layer_count as usize,
+        inner: (footer.mipmap_count,),
+    })?;
+
+    reader.seek(SeekFrom::Start(0)).await?;
+    let mut data = vec![0u8; data_size as usize];
+    reader.read_exact(&mut data).await?;
+
+    Ok(NutexbFile {
+        data,
+        layer_mipmaps,
+        footer,
+    })
+}
+
+pub async fn read_from_file(path: PathBuf, options: ReadOptions) -> BinResult<NutexbFile> {
+    let file_size = tokio::fs::metadata(&path).await?.len();
+    check_file_size(file_size, options)?;
+
+    NutexbFile::from_vec_with_options(tokio::fs::read(&path).await?, options)
+}
+
+pub async fn write<W: AsyncWrite + Unpin>(
+    nutexb: &NutexbFile,
+    writer: &mut W,
+    mode: WriteMode,
+) -> BinResult<()> {
+    let tail = nutexb.layer_mipmaps_and_footer_bytes(mode)?;
+
+    writer.write_all(&nutexb.data).await?;
+    writer.write_all(&tail).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+pub async fn write_to_file(
+    nutexb: &NutexbFile,
+    path: PathBuf,
+    options: WriteFileOptions,
+) -> BinResult<()> {
+    let mut writer = Cursor::new(Vec::new());
+    nutexb.write_with_mode(&mut writer, options.mode)?;
+    let bytes = writer.into_inner();
+
+    // Replacing the file requires several blocking calls, so run them on a separate thread.
+    tokio::task::spawn_blocking(move || {
+        crate::fs::write_file_atomic(&path, &bytes, options.keep_backup)
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+    Ok(())
+}
@@ -0,0 +1,1183 @@
+//! Reading and writing texture entries in BNTX files.
+//!
+//! BNTX is the texture container used by the NintendoWare graphics libraries on the Switch.
+//! Textures are swizzled using the same Tegra X1 block linear layout as nutexb,
+//! so image data can usually be moved between the formats without any conversion.
+use binrw::{binrw, prelude::*, Endian, NullString};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use tegra_swizzle::{
+    block_height_mip0, div_round_up, surface::swizzled_surface_size, BlockHeight, SwizzleError,
+};
+
+use crate::{
+    convert::create_nutexb_from_swizzled,
+    swizzle::{deswizzle_surface, swizzle_surface, SurfaceLayout},
+    NutexbFile, NutexbFormat, Surface, SurfaceError, TextureName, TextureNameError,
+};
+
+const HEADER_SIZE: u64 = 0x20;
+const NX_HEADER_SIZE: u64 = 0x38;
+const MEMORY_POOL_SIZE: u64 = 0x150;
+const BLOCK_HEADER_SIZE: u64 = 0x10;
+const BRTI_SIZE: u64 = 0xA0;
+const TEXTURE_OBJECT_SIZE: u64 = 0x100;
+const DATA_ALIGNMENT: u64 = 0x1000;
+const MIPMAP_ALIGNMENT: u64 = 0x200;
+
+/// A BNTX file containing one or more textures like `"ui_chara_mario_00.bntx"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BntxFile {
+    /// The name of the file, which usually matches the file name without its extension.
+    pub name: String,
+
+    /// The textures in the file.
+    /// Texture names should be unique.
+    pub textures: Vec<BntxTexture>,
+}
+
+/// A single texture entry in a [BntxFile].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BntxTexture {
+    /// The name used to look up the texture like `"ui_chara_mario_00"`.
+    pub name: String,
+
+    /// The width of the texture in pixels.
+    pub width: u32,
+
+    /// The height of the texture in pixels.
+    pub height: u32,
+
+    /// The depth of the texture in pixels or 1 for 2D textures.
+    pub depth: u32,
+
+    /// The number of mipmaps or 1 for no mipmapping.
+    pub mipmap_count: u32,
+
+    /// The number of array layers. This is 6 for cube maps and 1 otherwise.
+    pub layer_count: u32,
+
+    /// The `nn::gfx::ImageFormat` value with the channel format in the high byte
+    /// and the channel type like unorm or srgb in the low byte.
+    pub image_format: u32,
+
+    /// The base 2 logarithm of the block height in GOBs used for swizzling the base mipmap.
+    pub block_height_log2: u32,
+
+    /// `true` if [data](#structfield.data) uses the Tegra X1 block linear layout
+    /// and `false` for a linear layout.
+    ///
+    /// Linear textures pad each row to an alignment that isn't stored in the file,
+    /// so they can be read but not converted to nutexb or written.
+    pub is_swizzled: bool,
+
+    /// The `nn::gfx::ImageDimension` like `1` for 2D textures or `3` for cube maps.
+    pub image_dimension: u32,
+
+    /// The source for the red, green, blue, and alpha channels when sampling the texture.
+    pub channel_swizzle: [u8; 4],
+
+    /// Combined image data for all array layers and mipmaps.
+    pub data: Vec<u8>,
+}
+
+/// Errors while creating a nutexb file from a BNTX texture.
+#[derive(Debug)]
+pub enum ReadBntxError {
+    /// The BNTX image format is not a recognized or supported nutexb format.
+    UnrecognizedFormat(u32),
+    /// The BNTX data uses a linear layout with padded rows instead of the block linear layout.
+    UnsupportedLinearLayout,
+    /// The BNTX data could not be swizzled.
+    SwizzleError(SwizzleError),
+    /// The texture name does not fit in the nutexb footer.
+    InvalidName(TextureNameError),
+}
+
+impl Display for ReadBntxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadBntxError::UnrecognizedFormat(format) => {
+                write!(f, "unrecognized BNTX image format 0x{format:04X}")
+            }
+            ReadBntxError::UnsupportedLinearLayout => {
+                write!(f, "BNTX textures with a linear layout are not supported")
+            }
+            ReadBntxError::SwizzleError(e) => write!(f, "failed to swizzle surface: {e}"),
+            ReadBntxError::InvalidName(e) => write!(f, "invalid texture name: {e}"),
+        }
+    }
+}
+
+impl From<SwizzleError> for ReadBntxError {
+    fn from(value: SwizzleError) -> Self {
+        Self::SwizzleError(value)
+    }
+}
+
+impl From<SurfaceError> for ReadBntxError {
+    fn from(value: SurfaceError) -> Self {
+        match value {
+            SurfaceError::UnsupportedFormat(format) => Self::UnrecognizedFormat(format.into()),
+            SurfaceError::SwizzleError(e) => Self::SwizzleError(e),
+            SurfaceError::InvalidName(e) => Self::InvalidName(e),
+            // Conversions don't write to caller provided buffers or copy linear data.
+            SurfaceError::BufferTooSmall {
+                expected_size,
+                actual_size,
+            }
+            | SurfaceError::DataSizeMismatch {
+                expected_size,
+                actual_size,
+            } => Self::SwizzleError(SwizzleError::NotEnoughData {
+                expected_size,
+                actual_size,
+            }),
+        }
+    }
+}
+
+impl From<TextureNameError> for ReadBntxError {
+    fn from(value: TextureNameError) -> Self {
+        Self::InvalidName(value)
+    }
+}
+
+impl Error for ReadBntxError {}
+
+#[binrw]
+#[brw(little, magic = b"BNTX\0\0\0\0")]
+struct BntxHeader {
+    version: u32,
+    byte_order_mark: u16,
+    alignment_log2: u8,
+    address_size: u8,
+    file_name_offset: u32,
+    flags: u16,
+    first_block_offset: u16,
+    relocation_table_offset: u32,
+    file_size: u32,
+}
+
+#[binrw]
+#[brw(little, magic = b"NX  ")]
+struct NxHeader {
+    texture_count: u32,
+    texture_info_array_offset: u64,
+    texture_data_offset: u64,
+    dictionary_offset: u64,
+    memory_pool_offset: u64,
+    current_memory_pool_offset: u64,
+    base_memory_pool_offset: u32,
+    reserved: u32,
+}
+
+#[binrw]
+#[brw(little)]
+struct BlockHeader {
+    magic: [u8; 4],
+    next_block_offset: u32,
+    block_size: u32,
+    reserved: u32,
+}
+
+#[binrw]
+#[brw(little, magic = b"BRTI")]
+struct TextureInfo {
+    next_block_offset: u32,
+    block_size: u32,
+    reserved0: u32,
+    flags: u8,
+    storage_dimension: u8,
+    tile_mode: u16,
+    swizzle: u16,
+    mipmap_count: u16,
+    sample_count: u32,
+    image_format: u32,
+    gpu_access_flags: u32,
+    width: u32,
+    height: u32,
+    depth: u32,
+    layer_count: u32,
+    texture_layout: u32,
+    texture_layout2: u32,
+    reserved1: [u8; 20],
+    image_size: u32,
+    alignment: u32,
+    channel_swizzle: [u8; 4],
+    image_dimension: u32,
+    name_offset: u64,
+    parent_offset: u64,
+    mipmap_offsets_offset: u64,
+    user_data_offset: u64,
+    texture_offset: u64,
+    texture_view_offset: u64,
+    descriptor_slot_offset: u64,
+    user_data_dictionary_offset: u64,
+}
+
+#[binrw]
+#[brw(little)]
+struct RelocationSection {
+    pointer: u64,
+    position: u32,
+    size: u32,
+    entry_index: u32,
+    entry_count: u32,
+}
+
+#[binrw]
+#[brw(little)]
+struct RelocationEntry {
+    position: u32,
+    struct_count: u16,
+    offset_count: u8,
+    padding_count: u8,
+}
+
+// Strings are prefixed with their length and also null terminated.
+fn read_bin_string<R: Read + Seek>(reader: &mut R, offset: u64) -> BinResult<String> {
+    reader.seek(SeekFrom::Start(offset + 2))?;
+    let value: NullString = reader.read_le()?;
+    Ok(value.to_string())
+}
+
+impl BinRead for BntxFile {
+    type Args<'arg> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let file_size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let header: BntxHeader = reader.read_le()?;
+        let nx: NxHeader = reader.read_le()?;
+
+        // Check the count before allocating to avoid huge allocations for invalid files.
+        let info_array_size = nx.texture_count as u64 * 8;
+        if file_size.saturating_sub(nx.texture_info_array_offset) < info_array_size {
+            return Err(binrw::Error::AssertFail {
+                pos: HEADER_SIZE,
+                message: format!(
+                    "texture count {} does not fit in the {file_size} byte file",
+                    nx.texture_count
+                ),
+            });
+        }
+
+        reader.seek(SeekFrom::Start(nx.texture_info_array_offset))?;
+        let info_offsets: Vec<u64> = reader.read_le_args(
+            binrw::VecArgs::builder()
+                .count(nx.texture_count as usize)
+                .finalize(),
+        )?;
+
+        let mut textures = Vec::new();
+        for info_offset in info_offsets {
+            reader.seek(SeekFrom::Start(info_offset))?;
+            let info: TextureInfo = reader.read_le()?;
+
+            // The first mipmap offset points to the start of the image data for all layers and mipmaps.
+            reader.seek(SeekFrom::Start(info.mipmap_offsets_offset))?;
+            let data_offset: u64 = reader.read_le()?;
+            if file_size.saturating_sub(data_offset) < info.image_size as u64 {
+                return Err(binrw::Error::AssertFail {
+                    pos: info_offset,
+                    message: format!(
+                        "image size {} does not fit in the {file_size} byte file",
+                        info.image_size
+                    ),
+                });
+            }
+            reader.seek(SeekFrom::Start(data_offset))?;
+            let mut data = vec![0u8; info.image_size as usize];
+            reader.read_exact(&mut data)?;
+
+            textures.push(BntxTexture {
+                name: read_bin_string(reader, info.name_offset)?,
+                width: info.width,
+                height: info.height,
+                depth: info.depth,
+                mipmap_count: info.mipmap_count as u32,
+                layer_count: info.layer_count,
+                image_format: info.image_format,
+                block_height_log2: info.texture_layout & 0x7,
+                is_swizzled: info.tile_mode == 0,
+                image_dimension: info.image_dimension,
+                channel_swizzle: info.channel_swizzle,
+                data,
+            });
+        }
+
+        // The header offset points to the string itself rather than its length.
+        let name_offset = (header.file_name_offset as u64)
+            .checked_sub(2)
+            .ok_or_else(|| binrw::Error::AssertFail {
+                pos: 0x10,
+                message: format!("invalid file name offset {}", header.file_name_offset),
+            })?;
+        let name = read_bin_string(reader, name_offset)?;
+
+        Ok(Self { name, textures })
+    }
+}
+
+impl BinWrite for BntxFile {
+    type Args<'arg> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        // Write to a buffer first since the layout uses absolute offsets.
+        let mut buffer = Cursor::new(Vec::new());
+        write_bntx(self, &mut buffer)?;
+        writer.write_all(buffer.get_ref())?;
+        Ok(())
+    }
+}
+
+impl BntxFile {
+    /// Reads the [BntxFile] from the specified `reader`.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
+        reader.read_le::<BntxFile>()
+    }
+
+    /// Reads the [BntxFile] from the specified `path`.
+    /// The entire file is buffered to improve performance.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<BntxFile, binrw::Error> {
+        let mut file = Cursor::new(std::fs::read(path)?);
+        file.read_le::<BntxFile>()
+    }
+
+    /// Writes the [BntxFile] to the specified `writer`.
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
+        self.write_le(writer)
+    }
+
+    /// Writes the [BntxFile] to the specified `path`.
+    /// The entire file is buffered to improve performance.
+    ///
+    /// Like [NutexbFile::write_to_file], the data is written to a temporary file that replaces `path` once complete.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), binrw::Error> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write(&mut writer)?;
+        crate::fs::write_file_atomic(path.as_ref(), writer.get_ref(), false).map_err(Into::into)
+    }
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    offset.next_multiple_of(alignment)
+}
+
+fn write_bntx<W: Write + Seek>(bntx: &BntxFile, writer: &mut W) -> BinResult<()> {
+    // The row pitch for linear textures isn't known, so the mipmap offsets can't be calculated.
+    if let Some(texture) = bntx.textures.iter().find(|t| !t.is_swizzled) {
+        return Err(binrw::Error::AssertFail {
+            pos: 0,
+            message: format!(
+                "BNTX texture {:?} with a linear layout is not supported",
+                texture.name
+            ),
+        });
+    }
+
+    // The relocation table stores the number of pointers in a single byte.
+    let texture_pointer_count =
+        u8::try_from(bntx.textures.len()).map_err(|_| binrw::Error::AssertFail {
+            pos: 0,
+            message: format!(
+                "BNTX texture count {} exceeds the maximum of {}",
+                bntx.textures.len(),
+                u8::MAX
+            ),
+        })?;
+    let mipmap_counts = bntx
+        .textures
+        .iter()
+        .map(|texture| {
+            u8::try_from(texture.mipmap_count).map_err(|_| binrw::Error::AssertFail {
+                pos: 0,
+                message: format!(
+                    "BNTX texture {:?} mipmap count {} exceeds the maximum of {}",
+                    texture.name,
+                    texture.mipmap_count,
+                    u8::MAX
+                ),
+            })
+        })
+        .collect::<BinResult<Vec<_>>>()?;
+    let texture_count = bntx.textures.len() as u64;
+
+    // Calculate the offsets for each section in the same order as existing files.
+    let memory_pool_offset = HEADER_SIZE + NX_HEADER_SIZE;
+    let info_array_offset = memory_pool_offset + MEMORY_POOL_SIZE;
+
+    let string_table_offset = align(info_array_offset + 8 * texture_count, 8);
+    let mut string_offsets = Vec::new();
+    let mut offset = string_table_offset + BLOCK_HEADER_SIZE + 4;
+    for name in std::iter::once("")
+        .chain(std::iter::once(bntx.name.as_str()))
+        .chain(bntx.textures.iter().map(|t| t.name.as_str()))
+    {
+        string_offsets.push(offset);
+        offset = align(offset + 2 + name.len() as u64 + 1, 4);
+    }
+    let empty_string_offset = string_offsets[0];
+    let file_name_offset = string_offsets[1];
+    let texture_name_offsets = &string_offsets[2..];
+
+    let dictionary_offset = align(offset, 8);
+    let dictionary_nodes = build_dictionary(&bntx.textures)?;
+    let dictionary_size = 8 + 16 * dictionary_nodes.len() as u64;
+
+    // Each texture info is followed by its mipmap offsets and space for runtime objects.
+    let mut info_offsets = Vec::new();
+    let mut offset = align(dictionary_offset + dictionary_size, 8);
+    for texture in &bntx.textures {
+        info_offsets.push(offset);
+        offset += BRTI_SIZE;
+        offset = align(offset + 8 * texture.mipmap_count as u64, 8);
+        offset += TEXTURE_OBJECT_SIZE * 2 + 8;
+    }
+
+    // Align the image data to the alignment in the header.
+    let data_block_offset = align(offset + BLOCK_HEADER_SIZE, DATA_ALIGNMENT) - BLOCK_HEADER_SIZE;
+    let mut data_offsets = Vec::new();
+    let mut offset = data_block_offset + BLOCK_HEADER_SIZE;
+    for texture in &bntx.textures {
+        offset = align(offset, MIPMAP_ALIGNMENT);
+        data_offsets.push(offset);
+        offset += texture.data.len() as u64;
+    }
+    let data_end = offset;
+
+    let relocation_table_offset = align(data_end, 8);
+
+    // Pointers in the data block are relocated separately from the rest of the file.
+    let mut main_entries = vec![
+        RelocationEntry {
+            position: (HEADER_SIZE + 8) as u32,
+            struct_count: 1,
+            offset_count: 4,
+            padding_count: 0,
+        },
+        RelocationEntry {
+            position: info_array_offset as u32,
+            struct_count: 1,
+            offset_count: texture_pointer_count,
+            padding_count: 0,
+        },
+        RelocationEntry {
+            position: (dictionary_offset + 16) as u32,
+            struct_count: dictionary_nodes.len() as u16,
+            offset_count: 1,
+            padding_count: 1,
+        },
+    ];
+    let mut data_entries = Vec::new();
+    for info_offset in &info_offsets {
+        main_entries.push(RelocationEntry {
+            position: (info_offset + 0x60) as u32,
+            struct_count: 1,
+            offset_count: 3,
+            padding_count: 0,
+        });
+        main_entries.push(RelocationEntry {
+            position: (info_offset + 0x80) as u32,
+            struct_count: 1,
+            offset_count: 3,
+            padding_count: 0,
+        });
+    }
+    for (info_offset, mipmap_count) in info_offsets.iter().zip(&mipmap_counts) {
+        data_entries.push(RelocationEntry {
+            position: (info_offset + BRTI_SIZE) as u32,
+            struct_count: 1,
+            offset_count: *mipmap_count,
+            padding_count: 0,
+        });
+    }
+
+    let relocation_table_size =
+        BLOCK_HEADER_SIZE + 0x18 * 2 + 8 * (main_entries.len() + data_entries.len()) as u64;
+    let file_size = relocation_table_offset + relocation_table_size;
+
+    BntxHeader {
+        version: 0x00040000,
+        byte_order_mark: 0xFEFF,
+        alignment_log2: DATA_ALIGNMENT.trailing_zeros() as u8,
+        address_size: 64,
+        file_name_offset: (file_name_offset + 2) as u32,
+        flags: 0,
+        first_block_offset: string_table_offset as u16,
+        relocation_table_offset: relocation_table_offset as u32,
+        file_size: file_size as u32,
+    }
+    .write(writer)?;
+
+    NxHeader {
+        texture_count: texture_count as u32,
+        texture_info_array_offset: info_array_offset,
+        texture_data_offset: data_block_offset,
+        dictionary_offset,
+        memory_pool_offset,
+        current_memory_pool_offset: 0,
+        base_memory_pool_offset: 0,
+        reserved: 0,
+    }
+    .write(writer)?;
+
+    writer.seek(SeekFrom::Start(info_array_offset))?;
+    info_offsets.write_le(writer)?;
+
+    writer.seek(SeekFrom::Start(string_table_offset))?;
+    BlockHeader {
+        magic: *b"_STR",
+        next_block_offset: (dictionary_offset - string_table_offset) as u32,
+        block_size: (dictionary_offset - string_table_offset) as u32,
+        reserved: 0,
+    }
+    .write(writer)?;
+    // The empty string isn't included in the count.
+    (string_offsets.len() as u32 - 1).write_le(writer)?;
+    for (name, offset) in std::iter::once("")
+        .chain(std::iter::once(bntx.name.as_str()))
+        .chain(bntx.textures.iter().map(|t| t.name.as_str()))
+        .zip(&string_offsets)
+    {
+        writer.seek(SeekFrom::Start(*offset))?;
+        (name.len() as u16).write_le(writer)?;
+        NullString::from(name).write(writer)?;
+    }
+
+    writer.seek(SeekFrom::Start(dictionary_offset))?;
+    writer.write_all(b"_DIC")?;
+    (dictionary_nodes.len() as i32 - 1).write_le(writer)?;
+    for node in &dictionary_nodes {
+        node.reference.write_le(writer)?;
+        node.left.write_le(writer)?;
+        node.right.write_le(writer)?;
+        let key_offset = node
+            .texture
+            .map(|i| texture_name_offsets[i])
+            .unwrap_or(empty_string_offset);
+        key_offset.write_le(writer)?;
+    }
+
+    for (i, texture) in bntx.textures.iter().enumerate() {
+        let info_offset = info_offsets[i];
+        let next_block_offset = info_offsets
+            .get(i + 1)
+            .copied()
+            .unwrap_or(data_block_offset)
+            - info_offset;
+        let mipmap_offsets_offset = info_offset + BRTI_SIZE;
+        let texture_offset = align(mipmap_offsets_offset + 8 * texture.mipmap_count as u64, 8);
+
+        writer.seek(SeekFrom::Start(info_offset))?;
+        TextureInfo {
+            next_block_offset: next_block_offset as u32,
+            block_size: BRTI_SIZE as u32,
+            reserved0: 0,
+            flags: 1,
+            storage_dimension: if texture.depth > 1 { 3 } else { 2 },
+            tile_mode: 0,
+            swizzle: 0,
+            mipmap_count: u16::from(mipmap_counts[i]),
+            sample_count: 1,
+            image_format: texture.image_format,
+            gpu_access_flags: 0x20,
+            width: texture.width,
+            height: texture.height,
+            depth: texture.depth,
+            layer_count: texture.layer_count,
+            texture_layout: texture.block_height_log2,
+            texture_layout2: 0x00010007,
+            reserved1: [0; 20],
+            image_size: texture.data.len() as u32,
+            alignment: MIPMAP_ALIGNMENT as u32,
+            channel_swizzle: texture.channel_swizzle,
+            image_dimension: texture.image_dimension,
+            name_offset: texture_name_offsets[i],
+            parent_offset: HEADER_SIZE,
+            mipmap_offsets_offset,
+            user_data_offset: 0,
+            texture_offset,
+            texture_view_offset: texture_offset + TEXTURE_OBJECT_SIZE,
+            descriptor_slot_offset: texture_offset + TEXTURE_OBJECT_SIZE * 2,
+            user_data_dictionary_offset: 0,
+        }
+        .write(writer)?;
+
+        let mipmap_offsets: Vec<u64> = texture
+            .mipmap_offsets()
+            .into_iter()
+            .map(|o| data_offsets[i] + o)
+            .collect();
+        mipmap_offsets.write_le(writer)?;
+
+        // Reserve zeroed space for the runtime texture objects.
+        writer.seek(SeekFrom::Start(texture_offset))?;
+        writer.write_all(&[0u8; (TEXTURE_OBJECT_SIZE * 2 + 8) as usize])?;
+    }
+
+    writer.seek(SeekFrom::Start(data_block_offset))?;
+    BlockHeader {
+        magic: *b"BRTD",
+        next_block_offset: 0,
+        block_size: (data_end - data_block_offset) as u32,
+        reserved: 0,
+    }
+    .write(writer)?;
+    for (texture, offset) in bntx.textures.iter().zip(&data_offsets) {
+        writer.seek(SeekFrom::Start(*offset))?;
+        writer.write_all(&texture.data)?;
+    }
+
+    writer.seek(SeekFrom::Start(relocation_table_offset))?;
+    writer.write_all(b"_RLT")?;
+    (relocation_table_offset as u32).write_le(writer)?;
+    2u32.write_le(writer)?;
+    0u32.write_le(writer)?;
+    RelocationSection {
+        pointer: 0,
+        position: 0,
+        size: data_block_offset as u32,
+        entry_index: 0,
+        entry_count: main_entries.len() as u32,
+    }
+    .write(writer)?;
+    RelocationSection {
+        pointer: 0,
+        position: data_block_offset as u32,
+        size: (data_end - data_block_offset) as u32,
+        entry_index: main_entries.len() as u32,
+        entry_count: data_entries.len() as u32,
+    }
+    .write(writer)?;
+    main_entries.write_le(writer)?;
+    data_entries.write_le(writer)?;
+
+    Ok(())
+}
+
+struct DictionaryNode {
+    reference: u32,
+    left: u16,
+    right: u16,
+    texture: Option<usize>,
+}
+
+fn key_bit(key: &[u8], bit: u32) -> bool {
+    // Bits are indexed starting from the last character.
+    let byte = (bit / 8) as usize;
+    byte < key.len() && (key[key.len() - 1 - byte] >> (bit % 8)) & 1 == 1
+}
+
+// Build the radix tree used to look up textures by name.
+fn build_dictionary(textures: &[BntxTexture]) -> BinResult<Vec<DictionaryNode>> {
+    let mut nodes = vec![DictionaryNode {
+        reference: u32::MAX,
+        left: 0,
+        right: 0,
+        texture: None,
+    }];
+    let key = |nodes: &[DictionaryNode], i: usize| {
+        nodes[i]
+            .texture
+            .map(|t| textures[t].name.as_bytes())
+            .unwrap_or_default()
+    };
+    let child = |node: &DictionaryNode, name: &[u8]| {
+        if key_bit(name, node.reference) {
+            node.right as usize
+        } else {
+            node.left as usize
+        }
+    };
+
+    for (i, texture) in textures.iter().enumerate() {
+        let name = texture.name.as_bytes();
+        let index = nodes.len();
+
+        // The root node has a reference of -1, so compare as signed integers.
+        let mut previous = 0;
+        let mut current = nodes[0].left as usize;
+        while (nodes[previous].reference as i32) < (nodes[current].reference as i32) {
+            previous = current;
+            current = child(&nodes[current], name);
+        }
+
+        let similar = key(&nodes, current);
+        let bit_count = name.len().max(similar.len()) as u32 * 8;
+        let reference = (0..bit_count)
+            .find(|b| key_bit(name, *b) != key_bit(similar, *b))
+            .ok_or_else(|| binrw::Error::AssertFail {
+                pos: 0,
+                message: format!("duplicate or empty BNTX texture name {:?}", texture.name),
+            })?;
+
+        let mut previous = 0;
+        let mut current = nodes[0].left as usize;
+        while (nodes[previous].reference as i32) < (nodes[current].reference as i32)
+            && nodes[current].reference < reference
+        {
+            previous = current;
+            current = child(&nodes[current], name);
+        }
+
+        let (left, right) = if key_bit(name, reference) {
+            (current as u16, index as u16)
+        } else {
+            (index as u16, current as u16)
+        };
+        nodes.push(DictionaryNode {
+            reference,
+            left,
+            right,
+            texture: Some(i),
+        });
+
+        if previous == 0 {
+            nodes[0].left = index as u16;
+        } else if key_bit(name, nodes[previous].reference) {
+            nodes[previous].right = index as u16;
+        } else {
+            nodes[previous].left = index as u16;
+        }
+    }
+
+    Ok(nodes)
+}
+
+impl BntxTexture {
+    fn block_height_mip0(&self) -> Option<BlockHeight> {
+        BlockHeight::new(1 << self.block_height_log2)
+    }
+
+    // Offsets for the mipmaps of the first layer relative to the start of the data.
+    fn mipmap_offsets(&self) -> Vec<u64> {
+        let Some(format) = nutexb_format(self.image_format) else {
+            return vec![0; self.mipmap_count as usize];
+        };
+
+        (0..self.mipmap_count)
+            .map(|mip| {
+                swizzled_surface_size(
+                    self.width,
+                    self.height,
+                    self.depth,
+                    format.block_dim(),
+                    self.block_height_mip0(),
+                    format.bytes_per_pixel(),
+                    mip,
+                    1,
+                ) as u64
+            })
+            .collect()
+    }
+}
+
+fn nutexb_format(format: u32) -> Option<NutexbFormat> {
+    match format {
+        0x0201 => Some(NutexbFormat::R8Unorm),
+        0x0b01 => Some(NutexbFormat::R8G8B8A8Unorm),
+        0x0b06 => Some(NutexbFormat::R8G8B8A8Srgb),
+        0x1905 => Some(NutexbFormat::R32G32B32A32Float),
+        0x0c01 => Some(NutexbFormat::B8G8R8A8Unorm),
+        0x0c06 => Some(NutexbFormat::B8G8R8A8Srgb),
+        0x1505 => Some(NutexbFormat::R16G16B16A16Float),
+        0x1a01 => Some(NutexbFormat::BC1Unorm),
+        0x1a06 => Some(NutexbFormat::BC1Srgb),
+        0x1b01 => Some(NutexbFormat::BC2Unorm),
+        0x1b06 => Some(NutexbFormat::BC2Srgb),
+        0x1c01 => Some(NutexbFormat::BC3Unorm),
+        0x1c06 => Some(NutexbFormat::BC3Srgb),
+        0x1d01 => Some(NutexbFormat::BC4Unorm),
+        0x1d02 => Some(NutexbFormat::BC4Snorm),
+        0x1e01 => Some(NutexbFormat::BC5Unorm),
+        0x1e02 => Some(NutexbFormat::BC5Snorm),
+        0x1f0a => Some(NutexbFormat::BC6Ufloat),
+        0x1f05 => Some(NutexbFormat::BC6Sfloat),
+        0x2001 => Some(NutexbFormat::BC7Unorm),
+        0x2006 => Some(NutexbFormat::BC7Srgb),
+        _ => None,
+    }
+}
+
+fn bntx_format(format: NutexbFormat) -> Option<u32> {
+    Some(match format {
+        NutexbFormat::R8Unorm => 0x0201,
+        NutexbFormat::R8G8B8A8Unorm => 0x0b01,
+        NutexbFormat::R8G8B8A8Srgb => 0x0b06,
+        NutexbFormat::R32G32B32A32Float => 0x1905,
+        NutexbFormat::B8G8R8A8Unorm => 0x0c01,
+        NutexbFormat::B8G8R8A8Srgb => 0x0c06,
+        NutexbFormat::R16G16B16A16Float => 0x1505,
+        NutexbFormat::BC1Unorm => 0x1a01,
+        NutexbFormat::BC1Srgb => 0x1a06,
+        NutexbFormat::BC2Unorm => 0x1b01,
+        NutexbFormat::BC2Srgb => 0x1b06,
+        NutexbFormat::BC3Unorm => 0x1c01,
+        NutexbFormat::BC3Srgb => 0x1c06,
+        NutexbFormat::BC4Unorm => 0x1d01,
+        NutexbFormat::BC4Snorm => 0x1d02,
+        NutexbFormat::BC5Unorm => 0x1e01,
+        NutexbFormat::BC5Snorm => 0x1e02,
+        NutexbFormat::BC6Ufloat => 0x1f0a,
+        NutexbFormat::BC6Sfloat => 0x1f05,
+        NutexbFormat::BC7Unorm => 0x2001,
+        NutexbFormat::BC7Srgb => 0x2006,
+        NutexbFormat::Unknown(_) => return None,
+    })
+}
+
+pub fn create_nutexb(texture: &BntxTexture) -> Result<NutexbFile, ReadBntxError> {
+    let image_format = nutexb_format(texture.image_format)
+        .ok_or(ReadBntxError::UnrecognizedFormat(texture.image_format))?;
+
+    let surface = Surface {
+        width: texture.width,
+        height: texture.height,
+        depth: texture.depth,
+        image_data: texture.data.as_slice(),
+        mipmap_count: texture.mipmap_count,
+        layer_count: texture.layer_count,
+        image_format,
+    };
+
+    if !texture.is_swizzled {
+        return Err(ReadBntxError::UnsupportedLinearLayout);
+    }
+
+    // Nutexb always infers the block height from the dimensions.
+    let block_height = texture.block_height_mip0();
+    let size = swizzled_surface_size(
+        texture.width,
+        texture.height,
+        texture.depth,
+        image_format.block_dim(),
+        block_height,
+        image_format.bytes_per_pixel(),
+        texture.mipmap_count,
+        texture.layer_count,
+    );
+    let default_size = swizzled_surface_size(
+        texture.width,
+        texture.height,
+        texture.depth,
+        image_format.block_dim(),
+        None,
+        image_format.bytes_per_pixel(),
+        texture.mipmap_count,
+        texture.layer_count,
+    );
+    let default_block_height =
+        block_height_mip0(div_round_up(texture.height, image_format.block_height()));
+
+    if (texture.depth > 1 || block_height == Some(default_block_height))
+        && texture.data.len() >= default_size
+    {
+        // The swizzled data is already in the expected layout.
+        Ok(create_nutexb_from_swizzled(
+            Surface {
+                width: surface.width,
+                height: surface.height,
+                depth: surface.depth,
+                image_data: texture.data[..default_size].to_vec(),
+                mipmap_count: surface.mipmap_count,
+                layer_count: surface.layer_count,
+                image_format,
+            },
+            TextureName::new(texture.name.clone())?,
+        ))
+    } else {
+        // Other block heights require swizzling the data again.
+        let layout = SurfaceLayout {
+            width: texture.width,
+            height: texture.height,
+            depth: texture.depth,
+            block_dim: image_format.block_dim(),
+            block_height_mip0: block_height,
+            bytes_per_pixel: image_format.bytes_per_pixel(),
+            mipmap_count: texture.mipmap_count,
+            layer_count: texture.layer_count,
+        };
+        let data = deswizzle_surface(layout, texture.data.get(..size).unwrap_or(&texture.data))?;
+        NutexbFile::from_surface(
+            Surface {
+                width: surface.width,
+                height: surface.height,
+                depth: surface.depth,
+                image_data: data,
+                mipmap_count: surface.mipmap_count,
+                layer_count: surface.layer_count,
+                image_format,
+            },
+            texture.name.clone(),
+        )
+        .map_err(Into::into)
+    }
+}
+
+pub fn create_bntx_texture(nutexb: &NutexbFile) -> Result<BntxTexture, SurfaceError> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    let image_format = bntx_format(format).ok_or(SurfaceError::UnsupportedFormat(format))?;
+
+    // Unswizzled nutexb files are converted to match the more common swizzled layout.
+    let data = if footer.unk3 == 0x1000 {
+        let size = swizzled_surface_size(
+            footer.width,
+            footer.height,
+            footer.depth,
+            format.block_dim(),
+            None,
+            format.bytes_per_pixel(),
+            footer.mipmap_count,
+            footer.layer_count,
+        );
+        if nutexb.data.len() < size {
+            return Err(SurfaceError::SwizzleError(SwizzleError::NotEnoughData {
+                expected_size: size,
+                actual_size: nutexb.data.len(),
+            }));
+        }
+        nutexb.data[..size].to_vec()
+    } else {
+        swizzle_surface(footer.layout(), &nutexb.data)?
+    };
+
+    let block_height = if footer.depth > 1 {
+        BlockHeight::One
+    } else {
+        block_height_mip0(div_round_up(footer.height, format.block_height()))
+    };
+
+    let image_dimension = if footer.depth > 1 {
+        2
+    } else if footer.layer_count == 6 {
+        3
+    } else if footer.layer_count > 1 {
+        5
+    } else {
+        1
+    };
+
+    Ok(BntxTexture {
+        name: footer.name().to_string(),
+        width: footer.width,
+        height: footer.height,
+        depth: footer.depth,
+        mipmap_count: footer.mipmap_count,
+        layer_count: footer.layer_count,
+        image_format,
+        block_height_log2: (block_height as u32).trailing_zeros(),
+        is_swizzled: true,
+        image_dimension,
+        channel_swizzle: [2, 3, 4, 5],
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture(name: &str) -> BntxTexture {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![0u8; 4 * 4 * 4],
+            mipmap_count: 1,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        NutexbFile::from_surface(surface, name)
+            .unwrap()
+            .to_bntx_texture()
+            .unwrap()
+    }
+
+    fn bntx_bytes(bntx: &BntxFile) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        bntx.write(&mut writer).unwrap();
+        writer.into_inner()
+    }
+
+    fn nodes(names: &[&str]) -> Vec<(u32, u16, u16)> {
+        let textures: Vec<_> = names.iter().map(|n| texture(n)).collect();
+        build_dictionary(&textures)
+            .unwrap()
+            .iter()
+            .map(|n| (n.reference, n.left, n.right))
+            .collect()
+    }
+
+    // Look up a name the same way as nn::util::ResDic.
+    fn find(dictionary: &[u8], bytes: &[u8], name: &str) -> Option<usize> {
+        let node = |i: usize| {
+            let start = 8 + i * 16;
+            let reference = u32::from_le_bytes(dictionary[start..start + 4].try_into().unwrap());
+            let left = u16::from_le_bytes(dictionary[start + 4..start + 6].try_into().unwrap());
+            let right = u16::from_le_bytes(dictionary[start + 6..start + 8].try_into().unwrap());
+            let key_offset =
+                u64::from_le_bytes(dictionary[start + 8..start + 16].try_into().unwrap()) as usize;
+            let len = u16::from_le_bytes(bytes[key_offset..key_offset + 2].try_into().unwrap());
+            let key = &bytes[key_offset + 2..key_offset + 2 + len as usize];
+            (reference, left as usize, right as usize, key)
+        };
+
+        let (mut reference, left, _, _) = node(0);
+        let mut current = left;
+        loop {
+            let (next_reference, left, right, key) = node(current);
+            if (next_reference as i32) <= (reference as i32) {
+                return (key == name.as_bytes()).then(|| current - 1);
+            }
+            reference = next_reference;
+            current = if key_bit(name.as_bytes(), reference) {
+                right
+            } else {
+                left
+            };
+        }
+    }
+
+    #[test]
+    fn dictionary_single_texture() {
+        // 'a' differs from the empty root key at bit 0.
+        assert_eq!(vec![(u32::MAX, 1, 0), (0, 0, 1)], nodes(&["a"]));
+    }
+
+    #[test]
+    fn dictionary_multiple_textures() {
+        // 'a' is 0x61, 'b' is 0x62, and 'c' is 0x63.
+        assert_eq!(
+            vec![(u32::MAX, 1, 0), (0, 2, 1), (1, 0, 2)],
+            nodes(&["a", "b"])
+        );
+        assert_eq!(
+            vec![(u32::MAX, 1, 0), (0, 2, 3), (1, 0, 2), (1, 1, 3)],
+            nodes(&["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn dictionary_duplicate_name() {
+        let textures = vec![texture("a"), texture("a")];
+        assert!(build_dictionary(&textures).is_err());
+    }
+
+    #[test]
+    fn dictionary_lookup() {
+        let names = [
+            "ui_chara_mario_00",
+            "ui_chara_mario_01",
+            "ui_chara_luigi_00",
+            "ui_chara_link_00",
+            "ui_chara_mario",
+            "chara_1",
+            "chara_2",
+            "chara_3",
+            "chara_4",
+        ];
+        let bntx = BntxFile {
+            name: "ui_chara".to_string(),
+            textures: names.iter().map(|n| texture(n)).collect(),
+        };
+        let bytes = bntx_bytes(&bntx);
+
+        let dictionary_offset = u64::from_le_bytes(bytes[0x38..0x40].try_into().unwrap()) as usize;
+        let dictionary = &bytes[dictionary_offset..];
+        assert_eq!(b"_DIC", &dictionary[..4]);
+        assert_eq!(
+            names.len() as u32,
+            u32::from_le_bytes(dictionary[4..8].try_into().unwrap())
+        );
+
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(Some(i), find(dictionary, &bytes, name));
+        }
+        assert_eq!(None, find(dictionary, &bytes, "ui_chara_mario_02"));
+    }
+
+    #[test]
+    fn read_invalid_texture_count() {
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: vec![texture("a")],
+        };
+        let mut bytes = bntx_bytes(&bntx);
+        bytes[0x24..0x28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(BntxFile::read(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn read_invalid_file_name_offset() {
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: vec![texture("a")],
+        };
+        let mut bytes = bntx_bytes(&bntx);
+        bytes[0x10..0x14].copy_from_slice(&0u32.to_le_bytes());
+        assert!(BntxFile::read(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn linear_texture() {
+        let texture = BntxTexture {
+            is_swizzled: false,
+            ..texture("a")
+        };
+        assert!(matches!(
+            NutexbFile::from_bntx_texture(&texture),
+            Err(ReadBntxError::UnsupportedLinearLayout)
+        ));
+
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: vec![texture],
+        };
+        assert!(bntx.write(&mut Cursor::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn write_too_many_textures() {
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: (0..256).map(|i| texture(&format!("tex{i}"))).collect(),
+        };
+        assert!(bntx.write(&mut Cursor::new(Vec::new())).is_err());
+
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: (0..255).map(|i| texture(&format!("tex{i}"))).collect(),
+        };
+        assert!(bntx.write(&mut Cursor::new(Vec::new())).is_ok());
+    }
+
+    #[test]
+    fn write_too_many_mipmaps() {
+        let texture = BntxTexture {
+            mipmap_count: 256,
+            ..texture("a")
+        };
+        let bntx = BntxFile {
+            name: "file".to_string(),
+            textures: vec![texture],
+        };
+        assert!(bntx.write(&mut Cursor::new(Vec::new())).is_err());
+    }
+}
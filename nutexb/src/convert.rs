@@ -1,7 +1,9 @@
-use crate::{LayerMipmaps, NutexbFile, NutexbFooter, NutexbFormat};
-use binrw::NullString;
-use std::cmp::max;
-use tegra_swizzle::{div_round_up, surface::BlockDim};
+use crate::{
+    swizzle::{swizzle_surface_into, SurfaceLayout, SwizzleOptions},
+    LayerMipmaps, NutexbFile, NutexbFooter, NutexbFormat, TextureName, TextureNameError,
+};
+use std::{cmp::max, error::Error, fmt::Display};
+use tegra_swizzle::{div_round_up, surface::BlockDim, swizzle::deswizzled_mip_size};
 
 /// A surface describing a contiguous chunk of image data for the array layers and mipmaps used to create a [NutexbFile].
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -34,10 +36,170 @@ pub struct Surface<T> {
     pub image_format: NutexbFormat,
 }
 
+impl<T> Surface<T> {
+    /// The layout of the surface using the inferred block height.
+    pub(crate) fn layout(&self) -> SurfaceLayout {
+        SurfaceLayout {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            block_dim: self.image_format.block_dim(),
+            block_height_mip0: None,
+            bytes_per_pixel: self.image_format.bytes_per_pixel(),
+            mipmap_count: self.mipmap_count,
+            layer_count: self.layer_count,
+        }
+    }
+}
+
+/// Errors while swizzling or deswizzling image data.
+#[derive(Debug)]
+pub enum SurfaceError {
+    /// The image format is [NutexbFormat::Unknown], so the size and layout of the data isn't known.
+    UnsupportedFormat(NutexbFormat),
+    /// The image data could not be swizzled or deswizzled.
+    SwizzleError(tegra_swizzle::SwizzleError),
+    /// The name does not fit in the nutexb footer.
+    InvalidName(TextureNameError),
+    /// The output buffer is smaller than the size required for the image data.
+    BufferTooSmall {
+        expected_size: usize,
+        actual_size: usize,
+    },
+    /// The image data size does not match the size of all the layers and mipmaps.
+    DataSizeMismatch {
+        expected_size: usize,
+        actual_size: usize,
+    },
+}
+
+impl Display for SurfaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SurfaceError::UnsupportedFormat(format) => {
+                write!(f, "unsupported image format 0x{:X}", u32::from(*format))
+            }
+            SurfaceError::SwizzleError(e) => write!(f, "failed to swizzle surface: {e}"),
+            SurfaceError::InvalidName(e) => write!(f, "invalid name: {e}"),
+            SurfaceError::BufferTooSmall {
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "output buffer size {actual_size} is smaller than the required size {expected_size}"
+            ),
+            SurfaceError::DataSizeMismatch {
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "expected {expected_size} bytes of image data but found {actual_size} bytes"
+            ),
+        }
+    }
+}
+
+impl From<tegra_swizzle::SwizzleError> for SurfaceError {
+    fn from(value: tegra_swizzle::SwizzleError) -> Self {
+        Self::SwizzleError(value)
+    }
+}
+
+impl From<TextureNameError> for SurfaceError {
+    fn from(value: TextureNameError) -> Self {
+        Self::InvalidName(value)
+    }
+}
+
+impl Error for SurfaceError {}
+
+/// Returns an error for formats where the data layout isn't known.
+pub(crate) fn check_format(format: NutexbFormat) -> Result<(), SurfaceError> {
+    match format {
+        NutexbFormat::Unknown(_) => Err(SurfaceError::UnsupportedFormat(format)),
+        _ => Ok(()),
+    }
+}
+
+/// Returns `true` if the surface size can be calculated without overflow.
+/// This matches the checks tegra_swizzle uses before swizzling or deswizzling.
+pub(crate) fn is_valid_surface(
+    width: u32,
+    height: u32,
+    depth: u32,
+    bytes_per_pixel: u32,
+    mipmap_count: u32,
+) -> bool {
+    width
+        .checked_mul(height)
+        .and_then(|u| u.checked_mul(depth))
+        .and_then(|u| u.checked_mul(bytes_per_pixel))
+        .is_some()
+        && width.checked_mul(bytes_per_pixel).is_some()
+        && depth.checked_add(depth / 2).is_some()
+        && mipmap_count <= u32::BITS
+}
+
 pub fn create_nutexb<T: AsRef<[u8]>, S: Into<String>>(
     image: Surface<T>,
     name: S,
-) -> Result<NutexbFile, tegra_swizzle::SwizzleError> {
+) -> Result<NutexbFile, SurfaceError> {
+    create_nutexb_into(image, name, Vec::new())
+}
+
+pub fn create_nutexb_into<T: AsRef<[u8]>, S: Into<String>>(
+    image: Surface<T>,
+    name: S,
+    buffer: Vec<u8>,
+) -> Result<NutexbFile, SurfaceError> {
+    create_nutexb_with_options(image, name, buffer, SwizzleOptions::default())
+}
+
+pub fn create_nutexb_with_options<T: AsRef<[u8]>, S: Into<String>>(
+    image: Surface<T>,
+    name: S,
+    buffer: Vec<u8>,
+    options: SwizzleOptions,
+) -> Result<NutexbFile, SurfaceError> {
+    let name = TextureName::new(name)?;
+    create_nutexb_with_name(image, name, buffer, options)
+}
+
+/// Swizzles `image` like [create_nutexb_with_options] without validating an existing `name`.
+pub(crate) fn create_nutexb_with_name<T: AsRef<[u8]>>(
+    image: Surface<T>,
+    name: TextureName,
+    buffer: Vec<u8>,
+    options: SwizzleOptions,
+) -> Result<NutexbFile, SurfaceError> {
+    check_format(image.image_format)?;
+
+    let layout = SurfaceLayout {
+        block_height_mip0: options.block_height,
+        ..image.layout()
+    };
+    let mut data = buffer;
+    swizzle_surface_into(layout, image.image_data.as_ref(), &mut data)?;
+
+    Ok(create_nutexb_from_swizzled(
+        Surface {
+            width: image.width,
+            height: image.height,
+            depth: image.depth,
+            image_data: data,
+            mipmap_count: image.mipmap_count,
+            layer_count: image.layer_count,
+            image_format: image.image_format,
+        },
+        name,
+    ))
+}
+
+/// Creates a [NutexbFile] from `image` with data already swizzled using the inferred block height.
+pub(crate) fn create_nutexb_from_swizzled(
+    image: Surface<Vec<u8>>,
+    name: TextureName,
+) -> NutexbFile {
     let width = image.width;
     let height = image.height;
     let depth = image.depth;
@@ -60,27 +222,17 @@ pub fn create_nutexb<T: AsRef<[u8]>, S: Into<String>>(
         layer_count,
     );
 
-    let data = tegra_swizzle::surface::swizzle_surface(
-        width,
-        height,
-        depth,
-        image.image_data.as_ref(),
-        block_dim,
-        None,
-        bytes_per_pixel,
-        mip_count,
-        layer_count,
-    )?;
-
+    let data = image.image_data;
     let size = data.len() as u32;
 
     let unk2 = unk2(depth, layer_count);
 
-    Ok(NutexbFile {
+    NutexbFile {
         data,
         layer_mipmaps,
         footer: NutexbFooter {
-            string: NullString::from(name.into()),
+            name,
+            name_padding: Vec::new(),
             width,
             height,
             depth,
@@ -92,10 +244,44 @@ pub fn create_nutexb<T: AsRef<[u8]>, S: Into<String>>(
             data_size: size,
             version: (1, 2),
         },
-    })
+    }
 }
 
-fn unk2(depth: u32, layer_count: u32) -> u32 {
+/// Creates a [NutexbFile] from `image` with data in the linear layout used by unswizzled nutexbs.
+pub(crate) fn create_nutexb_from_linear(image: Surface<Vec<u8>>, name: TextureName) -> NutexbFile {
+    let layer_mipmaps = calculate_layer_mip_sizes(
+        image.width,
+        image.height,
+        image.depth,
+        image.image_format.block_dim(),
+        image.image_format.bytes_per_pixel(),
+        image.mipmap_count,
+        image.layer_count,
+    );
+
+    let size = image.image_data.len() as u32;
+
+    NutexbFile {
+        data: image.image_data,
+        layer_mipmaps,
+        footer: NutexbFooter {
+            name,
+            name_padding: Vec::new(),
+            width: image.width,
+            height: image.height,
+            depth: image.depth,
+            image_format: image.image_format,
+            unk2: 2,
+            mipmap_count: image.mipmap_count,
+            unk3: 0,
+            layer_count: image.layer_count,
+            data_size: size,
+            version: (2, 0),
+        },
+    }
+}
+
+pub(crate) fn unk2(depth: u32, layer_count: u32) -> u32 {
     // TODO: What does this value do?
     if depth > 1 {
         8
@@ -106,7 +292,26 @@ fn unk2(depth: u32, layer_count: u32) -> u32 {
     }
 }
 
-fn calculate_layer_mip_sizes(
+/// The size in bytes of a single layer of mipmap `mip` without any swizzling.
+pub(crate) fn mip_size(
+    width: u32,
+    height: u32,
+    depth: u32,
+    block_dim: BlockDim,
+    bytes_per_pixel: u32,
+    mip: u32,
+) -> usize {
+    // Halve dimensions for each mip level after the base level.
+    // The minimum mipmap size depends on the format.
+    let mip_dimension =
+        |x: u32, block: u32| max(div_round_up(x.checked_shr(mip).unwrap_or(0), block), 1);
+    let mip_width = mip_dimension(width, block_dim.width.get());
+    let mip_height = mip_dimension(height, block_dim.height.get());
+    let mip_depth = mip_dimension(depth, block_dim.depth.get());
+    deswizzled_mip_size(mip_width, mip_height, mip_depth, bytes_per_pixel)
+}
+
+pub(crate) fn calculate_layer_mip_sizes(
     width: u32,
     height: u32,
     depth: u32,
@@ -118,17 +323,9 @@ fn calculate_layer_mip_sizes(
     // Mipmaps are repeated for each layer.
     let layer = LayerMipmaps {
         mipmap_sizes: (0..mip_count)
-            .into_iter()
-            .map(|mip| {
-                // Halve dimensions for each mip level after the base level.
-                // The minimum mipmap size depends on the format.
-                let mip_width = max(div_round_up(width >> mip, block_dim.width.get()), 1);
-                let mip_height = max(div_round_up(height >> mip, block_dim.height.get()), 1);
-                let mip_depth = max(div_round_up(depth >> mip, block_dim.depth.get()), 1);
-
-                mip_width * mip_height * mip_depth * bytes_per_pixel
-            })
+            .map(|mip| mip_size(width, height, depth, block_dim, bytes_per_pixel, mip) as u32)
             .collect(),
+        padding: Vec::new(),
     };
     vec![layer; layer_count as usize]
 }
@@ -136,37 +333,77 @@ fn calculate_layer_mip_sizes(
 pub fn create_nutexb_unswizzled<T: AsRef<[u8]>, S: Into<String>>(
     surface: &Surface<T>,
     name: S,
-) -> NutexbFile {
-    let width = surface.width;
-    let height = surface.height;
-    let depth = surface.depth;
+) -> Result<NutexbFile, SurfaceError> {
+    let name = TextureName::new(name)?;
+    check_format(surface.image_format)?;
+
+    // The linear layout has no padding, so the data must contain exactly the bytes for each layer and mipmap.
+    let size = surface.layout().deswizzled_size()?;
+    let image_data = surface.image_data.as_ref();
+    if image_data.len() != size {
+        return Err(SurfaceError::DataSizeMismatch {
+            expected_size: size,
+            actual_size: image_data.len(),
+        });
+    }
 
-    // TODO: Mipmaps and array layers?
-    let data = surface.image_data.as_ref().to_vec();
+    Ok(create_nutexb_from_linear(
+        Surface {
+            width: surface.width,
+            height: surface.height,
+            depth: surface.depth,
+            image_data: image_data.to_vec(),
+            mipmap_count: surface.mipmap_count,
+            layer_count: surface.layer_count,
+            image_format: surface.image_format,
+        },
+        name,
+    ))
+}
 
-    let image_format = surface.image_format;
-    let bytes_per_pixel = image_format.bytes_per_pixel();
-    let block_dim = image_format.block_dim();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let layer_mipmaps =
-        calculate_layer_mip_sizes(width, height, depth, block_dim, bytes_per_pixel, 1, 1);
+    fn surface(size: usize) -> Surface<Vec<u8>> {
+        // 6 layers with mipmaps of 4x4, 2x2, and 1x1 pixels.
+        Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![0u8; size],
+            mipmap_count: 3,
+            layer_count: 6,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        }
+    }
 
-    let size = data.len() as u32;
-    NutexbFile {
-        data,
-        layer_mipmaps,
-        footer: NutexbFooter {
-            string: NullString::from(name.into()),
-            width,
-            height,
-            depth,
-            image_format,
-            unk2: 2,
-            mipmap_count: 1,
-            unk3: 0, // TODO: toggles swizzling?
-            layer_count: 1,
-            data_size: size,
-            version: (2, 0),
-        },
+    #[test]
+    fn create_nutexb_unswizzled_exact_size() {
+        let nutexb = create_nutexb_unswizzled(&surface(504), "tex").unwrap();
+        assert_eq!(504, nutexb.data.len());
+        assert_eq!(504, nutexb.footer.data_size);
+    }
+
+    #[test]
+    fn create_nutexb_unswizzled_too_short() {
+        assert!(matches!(
+            create_nutexb_unswizzled(&surface(503), "tex"),
+            Err(SurfaceError::DataSizeMismatch {
+                expected_size: 504,
+                actual_size: 503
+            })
+        ));
+    }
+
+    #[test]
+    fn create_nutexb_unswizzled_too_long() {
+        assert!(matches!(
+            create_nutexb_unswizzled(&surface(505), "tex"),
+            Err(SurfaceError::DataSizeMismatch {
+                expected_size: 504,
+                actual_size: 505
+            })
+        ));
     }
 }
@@ -4,23 +4,58 @@ use ddsfile::{
     AlphaMode, Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, FourCC, NewDxgiParams,
 };
 
-use crate::{NutexbFile, NutexbFormat, Surface};
+use tegra_swizzle::{div_round_up, swizzle::deswizzled_mip_size};
+
+use crate::{
+    convert::is_valid_surface,
+    decode::{linear_to_srgb, srgb_to_linear, unorm8},
+    NutexbFile, NutexbFormat, Surface, SurfaceError, TextureNameError,
+};
 
 /// Errors while creating a nutexb file from a DDS file.
 #[derive(Debug)]
 pub enum ReadDdsError {
     /// The DDS format is not a recognized or supported nutexb format.
     UnrecognizedFormat,
+    /// Premultiplied alpha can only be removed from uncompressed formats with 8-bit or 32-bit float alpha
+    /// and from BC1, BC2, BC3, and BC7 when using the `"encoder"` feature.
+    UnsupportedUnpremultiply(NutexbFormat),
+    /// The DDS data is smaller than the size required by the dimensions, format, mipmaps, and layers in the header.
+    NotEnoughData {
+        expected_size: usize,
+        actual_size: usize,
+    },
     /// The DDS data could not be swizzled.
     /// This usually means the DDS header does not accurately describe the image data.
     SwizzleError(tegra_swizzle::SwizzleError),
+    /// The name does not fit in the nutexb footer.
+    InvalidName(TextureNameError),
+    /// The block compressed data could not be decoded or encoded to remove premultiplied alpha.
+    #[cfg(feature = "encoder")]
+    CompressionError(image_dds::error::SurfaceError),
 }
 
 impl Display for ReadDdsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ReadDdsError::UnrecognizedFormat => write!(f, "unrecognized DDS format"),
+            ReadDdsError::UnsupportedUnpremultiply(format) => write!(
+                f,
+                "cannot remove premultiplied alpha from format {format:?}"
+            ),
+            ReadDdsError::NotEnoughData {
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "DDS header expects {expected_size} bytes of image data but found {actual_size} bytes"
+            ),
             ReadDdsError::SwizzleError(e) => write!(f, "failed to swizzle surface: {e}"),
+            ReadDdsError::InvalidName(e) => write!(f, "invalid name: {e}"),
+            #[cfg(feature = "encoder")]
+            ReadDdsError::CompressionError(e) => {
+                write!(f, "failed to decode or encode surface: {e}")
+            }
         }
     }
 }
@@ -31,20 +66,242 @@ impl From<tegra_swizzle::SwizzleError> for ReadDdsError {
     }
 }
 
+#[cfg(feature = "encoder")]
+impl From<image_dds::error::SurfaceError> for ReadDdsError {
+    fn from(value: image_dds::error::SurfaceError) -> Self {
+        Self::CompressionError(value)
+    }
+}
+
+impl From<SurfaceError> for ReadDdsError {
+    fn from(value: SurfaceError) -> Self {
+        match value {
+            SurfaceError::UnsupportedFormat(_) => Self::UnrecognizedFormat,
+            SurfaceError::SwizzleError(e) => Self::SwizzleError(e),
+            SurfaceError::InvalidName(e) => Self::InvalidName(e),
+            // Conversions don't write to caller provided buffers or copy linear data.
+            SurfaceError::BufferTooSmall {
+                expected_size,
+                actual_size,
+            }
+            | SurfaceError::DataSizeMismatch {
+                expected_size,
+                actual_size,
+            } => Self::SwizzleError(tegra_swizzle::SwizzleError::NotEnoughData {
+                expected_size,
+                actual_size,
+            }),
+        }
+    }
+}
+
 impl Error for ReadDdsError {}
 
-pub fn create_surface(dds: &Dds) -> Result<Surface<&[u8]>, ReadDdsError> {
+/// Options for creating a nutexb file from a DDS file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DdsOptions {
+    /// Divide the color channels by alpha for DDS files with premultiplied alpha.
+    ///
+    /// This is supported for uncompressed formats and for BC1, BC2, BC3, and BC7 with the `"encoder"` feature.
+    /// Compressed formats are decoded and encoded again, which slightly reduces quality.
+    /// DDS files with straight or opaque alpha are not modified.
+    pub unpremultiply_alpha: bool,
+
+    /// Reduce the mipmap count to the number of mipmaps present in the DDS data
+    /// instead of returning [ReadDdsError::NotEnoughData].
+    pub allow_truncated_mipmaps: bool,
+}
+
+pub fn create_surface(dds: &Dds, options: DdsOptions) -> Result<Surface<&[u8]>, ReadDdsError> {
+    let width = dds.get_width();
+    let height = dds.get_height();
+    let depth = dds.get_depth();
+    let layer_count = layer_count(dds);
+    let image_format = dds_image_format(dds).ok_or(ReadDdsError::UnrecognizedFormat)?;
+
+    let mipmap_count = dds.get_num_mipmap_levels();
+    let bytes_per_pixel = image_format.bytes_per_pixel();
+
+    // Check the header before calculating any sizes to avoid overflow.
+    if !is_valid_surface(width, height, depth, bytes_per_pixel, mipmap_count) {
+        return Err(ReadDdsError::SwizzleError(
+            tegra_swizzle::SwizzleError::InvalidSurface {
+                width,
+                height,
+                depth,
+                bytes_per_pixel,
+                mipmap_count,
+            },
+        ));
+    }
+
+    // Find the most mipmaps for each layer that fit in the data,
+    // since some tools write a mipmap count that doesn't match the data.
+    let block_dim = image_format.block_dim();
+    let mut present_mipmaps = 0;
+    let mut layer_size = 0usize;
+    let mut expected_size = 0;
+    for mip in 0..mipmap_count {
+        layer_size += deswizzled_mip_size(
+            div_round_up(width >> mip, block_dim.width.get()).max(1),
+            div_round_up(height >> mip, block_dim.height.get()).max(1),
+            div_round_up(depth >> mip, block_dim.depth.get()).max(1),
+            bytes_per_pixel,
+        );
+        expected_size = layer_size.saturating_mul(layer_count as usize);
+        if expected_size <= dds.data.len() {
+            present_mipmaps = mip + 1;
+        }
+    }
+
+    let mipmap_count = if present_mipmaps == mipmap_count {
+        mipmap_count
+    } else if options.allow_truncated_mipmaps && present_mipmaps > 0 {
+        present_mipmaps
+    } else {
+        return Err(ReadDdsError::NotEnoughData {
+            expected_size,
+            actual_size: dds.data.len(),
+        });
+    };
+
     Ok(Surface {
-        width: dds.get_width(),
-        height: dds.get_height(),
-        depth: dds.get_depth(),
+        width,
+        height,
+        depth,
         image_data: &dds.data,
-        mipmap_count: dds.get_num_mipmap_levels(),
-        layer_count: layer_count(dds),
-        image_format: dds_image_format(dds).ok_or(ReadDdsError::UnrecognizedFormat)?,
+        mipmap_count,
+        layer_count,
+        image_format,
     })
 }
 
+/// The alpha mode from the DX10 header or legacy premultiplied formats like DXT2 and DXT4.
+pub fn alpha_mode(dds: &Dds) -> AlphaMode {
+    match &dds.header10 {
+        Some(header10) if header10.alpha_mode != AlphaMode::Unknown => header10.alpha_mode,
+        _ => {
+            let is_premultiplied = matches!(
+                dds.get_d3d_format(),
+                Some(D3DFormat::DXT2 | D3DFormat::DXT4)
+            ) || matches!(
+                dds.header.spf.fourcc.as_ref().map(|f| f.0),
+                Some(FourCC::DXT2 | FourCC::DXT4)
+            );
+            if is_premultiplied {
+                AlphaMode::PreMultiplied
+            } else {
+                AlphaMode::Unknown
+            }
+        }
+    }
+}
+
+/// Divides the color channels by alpha for all the layers and mipmaps in `surface`.
+pub fn unpremultiply_surface(surface: &Surface<&[u8]>) -> Result<Vec<u8>, ReadDdsError> {
+    let format = surface.image_format;
+    match format {
+        NutexbFormat::BC1Unorm
+        | NutexbFormat::BC1Srgb
+        | NutexbFormat::BC2Unorm
+        | NutexbFormat::BC2Srgb
+        | NutexbFormat::BC3Unorm
+        | NutexbFormat::BC3Srgb
+        | NutexbFormat::BC7Unorm
+        | NutexbFormat::BC7Srgb => unpremultiply_compressed(surface),
+        _ => unpremultiply_alpha(format, surface.image_data),
+    }
+}
+
+#[cfg(feature = "encoder")]
+fn unpremultiply_compressed(surface: &Surface<&[u8]>) -> Result<Vec<u8>, ReadDdsError> {
+    use image_dds::{Mipmaps, Quality, SurfaceRgba8};
+
+    let format = surface.image_format;
+    let image_format = crate::decode::bc_image_format(format)
+        .ok_or(ReadDdsError::UnsupportedUnpremultiply(format))?;
+
+    let rgba = image_dds::Surface {
+        width: surface.width,
+        height: surface.height,
+        depth: surface.depth,
+        layers: surface.layer_count,
+        mipmaps: surface.mipmap_count,
+        image_format,
+        data: surface.image_data,
+    }
+    .decode_rgba8()?;
+
+    // The decoded values are still sRGB encoded for sRGB formats.
+    let rgba_format = if format.is_srgb() {
+        NutexbFormat::R8G8B8A8Srgb
+    } else {
+        NutexbFormat::R8G8B8A8Unorm
+    };
+    let data = unpremultiply_alpha(rgba_format, &rgba.data)?;
+
+    let encoded = SurfaceRgba8 { data, ..rgba }.encode(
+        image_format,
+        Quality::Normal,
+        Mipmaps::FromSurface,
+    )?;
+    Ok(encoded.data)
+}
+
+#[cfg(not(feature = "encoder"))]
+fn unpremultiply_compressed(surface: &Surface<&[u8]>) -> Result<Vec<u8>, ReadDdsError> {
+    Err(ReadDdsError::UnsupportedUnpremultiply(surface.image_format))
+}
+
+pub fn unpremultiply_alpha(format: NutexbFormat, data: &[u8]) -> Result<Vec<u8>, ReadDdsError> {
+    let mut data = data.to_vec();
+    match format {
+        NutexbFormat::R8G8B8A8Unorm | NutexbFormat::B8G8R8A8Unorm => {
+            for pixel in data.chunks_exact_mut(4) {
+                let a = pixel[3] as u32;
+                for c in &mut pixel[..3] {
+                    if let Some(value) = (*c as u32 * 255 + a / 2).checked_div(a) {
+                        *c = value.min(255) as u8;
+                    }
+                }
+            }
+        }
+        NutexbFormat::R8G8B8A8Srgb | NutexbFormat::B8G8R8A8Srgb => {
+            // Alpha is premultiplied with the linear color values rather than the encoded values.
+            for pixel in data.chunks_exact_mut(4) {
+                let a = pixel[3] as f32 / 255.0;
+                if a > 0.0 {
+                    for c in &mut pixel[..3] {
+                        let value = srgb_to_linear(*c as f32 / 255.0) / a;
+                        *c = unorm8(linear_to_srgb(value.min(1.0)));
+                    }
+                }
+            }
+        }
+        NutexbFormat::R32G32B32A32Float => {
+            for pixel in data.chunks_exact_mut(16) {
+                let a = f32::from_le_bytes(pixel[12..16].try_into().unwrap());
+                if a > 0.0 {
+                    for c in pixel[..12].chunks_exact_mut(4) {
+                        let value = f32::from_le_bytes(c.try_into().unwrap()) / a;
+                        c.copy_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+        }
+        // Formats without alpha are unaffected by premultiplication.
+        NutexbFormat::R8Unorm
+        | NutexbFormat::BC4Unorm
+        | NutexbFormat::BC4Snorm
+        | NutexbFormat::BC5Unorm
+        | NutexbFormat::BC5Snorm
+        | NutexbFormat::BC6Ufloat
+        | NutexbFormat::BC6Sfloat => (),
+        _ => return Err(ReadDdsError::UnsupportedUnpremultiply(format)),
+    }
+    Ok(data)
+}
+
 fn layer_count(dds: &Dds) -> u32 {
     // Array layers for DDS are calculated differently for cube maps.
     if matches!(&dds.header10, Some(header10) if header10.misc_flag == ddsfile::MiscFlag::TEXTURECUBE)
@@ -74,6 +331,7 @@ fn image_format_from_dxgi(format: DxgiFormat) -> Option<NutexbFormat> {
         DxgiFormat::R32G32B32A32_Float => Some(NutexbFormat::R32G32B32A32Float),
         DxgiFormat::B8G8R8A8_UNorm => Some(NutexbFormat::B8G8R8A8Unorm),
         DxgiFormat::B8G8R8A8_UNorm_sRGB => Some(NutexbFormat::B8G8R8A8Srgb),
+        DxgiFormat::R16G16B16A16_Float => Some(NutexbFormat::R16G16B16A16Float),
         DxgiFormat::BC1_UNorm => Some(NutexbFormat::BC1Unorm),
         DxgiFormat::BC1_UNorm_sRGB => Some(NutexbFormat::BC1Srgb),
         DxgiFormat::BC2_UNorm => Some(NutexbFormat::BC2Unorm),
@@ -99,6 +357,8 @@ fn image_format_from_d3d(format: D3DFormat) -> Option<NutexbFormat> {
         D3DFormat::DXT3 => Some(NutexbFormat::BC2Unorm),
         D3DFormat::DXT4 => Some(NutexbFormat::BC3Unorm),
         D3DFormat::DXT5 => Some(NutexbFormat::BC3Unorm),
+        // D3D names packed formats from the most significant bits.
+        D3DFormat::A16B16G16R16F => Some(NutexbFormat::R16G16B16A16Float),
         _ => None,
     }
 }
@@ -144,13 +404,20 @@ impl From<NutexbFormat> for DxgiFormat {
             NutexbFormat::R32G32B32A32Float => Self::R32G32B32A32_Float,
             NutexbFormat::B8G8R8A8Unorm => Self::B8G8R8A8_UNorm,
             NutexbFormat::B8G8R8A8Srgb => Self::B8G8R8A8_UNorm_sRGB,
+            NutexbFormat::R16G16B16A16Float => Self::R16G16B16A16_Float,
+            NutexbFormat::Unknown(_) => Self::Unknown,
         }
     }
 }
 
-pub fn create_dds(nutexb: &NutexbFile) -> Result<Dds, tegra_swizzle::SwizzleError> {
+pub fn create_dds(nutexb: &NutexbFile, alpha_mode: Option<AlphaMode>) -> Result<Dds, SurfaceError> {
     let some_if_above_one = |x| if x > 0 { Some(x) } else { None };
 
+    // DDS stores mipmaps in a contiguous region of memory.
+    let data = nutexb.deswizzled_data()?;
+    let alpha_mode =
+        alpha_mode.unwrap_or_else(|| detect_alpha_mode(nutexb.footer.image_format, &data));
+
     // TODO: Avoid unwrap.
     let mut dds = Dds::new_dxgi(NewDxgiParams {
         height: nutexb.footer.height,
@@ -170,12 +437,398 @@ pub fn create_dds(nutexb: &NutexbFile) -> Result<Dds, tegra_swizzle::SwizzleErro
         } else {
             D3D10ResourceDimension::Texture2D
         },
-        alpha_mode: AlphaMode::Unknown, // TODO: Alpha mode?
+        alpha_mode,
     })
     .unwrap();
 
-    // DDS stores mipmaps in a contiguous region of memory.
-    dds.data = nutexb.deswizzled_data()?;
+    dds.data = data;
 
     Ok(dds)
 }
+
+fn detect_alpha_mode(format: NutexbFormat, data: &[u8]) -> AlphaMode {
+    let is_opaque = match format {
+        NutexbFormat::R8G8B8A8Unorm
+        | NutexbFormat::R8G8B8A8Srgb
+        | NutexbFormat::B8G8R8A8Unorm
+        | NutexbFormat::B8G8R8A8Srgb => data.chunks_exact(4).all(|p| p[3] == 255),
+        NutexbFormat::R32G32B32A32Float => data
+            .chunks_exact(16)
+            .all(|p| f32::from_le_bytes(p[12..16].try_into().unwrap()) >= 1.0),
+        NutexbFormat::R16G16B16A16Float => data
+            .chunks_exact(8)
+            .all(|p| crate::decode::f16_to_f32([p[6], p[7]]) >= 1.0),
+        NutexbFormat::BC1Unorm | NutexbFormat::BC1Srgb => data.chunks_exact(8).all(bc1_is_opaque),
+        NutexbFormat::BC2Unorm | NutexbFormat::BC2Srgb => data
+            .chunks_exact(16)
+            .all(|b| b[..8].iter().all(|a| *a == 0xFF)),
+        NutexbFormat::BC3Unorm | NutexbFormat::BC3Srgb => {
+            data.chunks_exact(16).all(|b| bc3_alpha_is_opaque(&b[..8]))
+        }
+        // Modes 0 to 3 don't encode alpha.
+        // Checking the alpha for other modes would require decoding the blocks.
+        NutexbFormat::BC7Unorm | NutexbFormat::BC7Srgb => {
+            return if data.chunks_exact(16).all(|b| b[0] & 0x0F != 0) {
+                AlphaMode::Opaque
+            } else {
+                AlphaMode::Unknown
+            };
+        }
+        // These formats have no alpha channel.
+        NutexbFormat::R8Unorm
+        | NutexbFormat::BC4Unorm
+        | NutexbFormat::BC4Snorm
+        | NutexbFormat::BC5Unorm
+        | NutexbFormat::BC5Snorm
+        | NutexbFormat::BC6Ufloat
+        | NutexbFormat::BC6Sfloat => true,
+        NutexbFormat::Unknown(_) => false,
+    };
+
+    if is_opaque {
+        AlphaMode::Opaque
+    } else {
+        AlphaMode::Straight
+    }
+}
+
+fn bc1_is_opaque(block: &[u8]) -> bool {
+    // Index 3 is transparent black when color0 <= color1.
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    color0 > color1 || (0..16).all(|i| (indices >> (i * 2)) & 0b11 != 3)
+}
+
+fn bc3_alpha_is_opaque(block: &[u8]) -> bool {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let palette: [u32; 8] = if a0 > a1 {
+        std::array::from_fn(|i| match i {
+            0 => a0,
+            1 => a1,
+            _ => ((8 - i as u32) * a0 + (i as u32 - 1) * a1) / 7,
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => a0,
+            1 => a1,
+            6 => 0,
+            7 => 255,
+            _ => ((6 - i as u32) * a0 + (i as u32 - 1) * a1) / 5,
+        })
+    };
+
+    let mut indices = [0u8; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    (0..16).all(|i| palette[((indices >> (i * 3)) & 0b111) as usize] == 255)
+}
+
+#[cfg(test)]
+mod tests {
+    use ddsfile::NewD3dParams;
+
+    use super::*;
+
+    fn d3d_dds(format: D3DFormat) -> Dds {
+        Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap()
+    }
+
+    fn dxgi_dds(format: DxgiFormat, alpha_mode: AlphaMode) -> Dds {
+        Dds::new_dxgi(NewDxgiParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format,
+            mipmap_levels: None,
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn alpha_mode_dxt2_dxt4() {
+        assert_eq!(
+            AlphaMode::PreMultiplied,
+            alpha_mode(&d3d_dds(D3DFormat::DXT2))
+        );
+        assert_eq!(
+            AlphaMode::PreMultiplied,
+            alpha_mode(&d3d_dds(D3DFormat::DXT4))
+        );
+        assert_eq!(
+            Some(NutexbFormat::BC2Unorm),
+            dds_image_format(&d3d_dds(D3DFormat::DXT2))
+        );
+        assert_eq!(
+            Some(NutexbFormat::BC3Unorm),
+            dds_image_format(&d3d_dds(D3DFormat::DXT4))
+        );
+    }
+
+    #[test]
+    fn alpha_mode_dxt3_dxt5() {
+        assert_eq!(AlphaMode::Unknown, alpha_mode(&d3d_dds(D3DFormat::DXT3)));
+        assert_eq!(AlphaMode::Unknown, alpha_mode(&d3d_dds(D3DFormat::DXT5)));
+    }
+
+    #[test]
+    fn alpha_mode_dx10_header() {
+        let dds = dxgi_dds(DxgiFormat::BC3_UNorm, AlphaMode::PreMultiplied);
+        assert_eq!(AlphaMode::PreMultiplied, alpha_mode(&dds));
+
+        let dds = dxgi_dds(DxgiFormat::BC3_UNorm, AlphaMode::Straight);
+        assert_eq!(AlphaMode::Straight, alpha_mode(&dds));
+    }
+
+    #[test]
+    fn unpremultiply_rgba8() {
+        let data = [64, 32, 0, 128, 10, 20, 30, 0, 255, 255, 255, 255];
+        assert_eq!(
+            vec![128, 64, 0, 128, 10, 20, 30, 0, 255, 255, 255, 255],
+            unpremultiply_alpha(NutexbFormat::R8G8B8A8Unorm, &data).unwrap()
+        );
+    }
+
+    #[test]
+    fn unpremultiply_rgba8_srgb() {
+        // Linear 0.5 premultiplied by alpha 0.2 is 0.1 or about 89 after encoding as sRGB.
+        // Dividing the encoded value would saturate to 255 instead.
+        let data = [89, 89, 89, 51, 10, 20, 30, 0];
+        assert_eq!(
+            vec![187, 187, 187, 51, 10, 20, 30, 0],
+            unpremultiply_alpha(NutexbFormat::R8G8B8A8Srgb, &data).unwrap()
+        );
+        assert_eq!(
+            vec![187, 187, 187, 51, 10, 20, 30, 0],
+            unpremultiply_alpha(NutexbFormat::B8G8R8A8Srgb, &data).unwrap()
+        );
+    }
+
+    #[test]
+    fn unpremultiply_rgba_f32() {
+        let data: Vec<u8> = [0.25f32, 0.5, 0.0, 0.5, 1.0, 1.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        let expected: Vec<u8> = [0.5f32, 1.0, 0.0, 0.5, 1.0, 1.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        assert_eq!(
+            expected,
+            unpremultiply_alpha(NutexbFormat::R32G32B32A32Float, &data).unwrap()
+        );
+    }
+
+    #[test]
+    fn unpremultiply_no_alpha() {
+        assert_eq!(
+            vec![1u8; 16],
+            unpremultiply_alpha(NutexbFormat::BC4Unorm, &[1u8; 16]).unwrap()
+        );
+    }
+
+    #[test]
+    fn unpremultiply_compressed() {
+        assert!(matches!(
+            unpremultiply_alpha(NutexbFormat::BC3Unorm, &[0u8; 16]),
+            Err(ReadDdsError::UnsupportedUnpremultiply(
+                NutexbFormat::BC3Unorm
+            ))
+        ));
+    }
+
+    #[test]
+    fn from_dds_unpremultiply_alpha() {
+        let mut dds = dxgi_dds(DxgiFormat::R8G8B8A8_UNorm, AlphaMode::PreMultiplied);
+        dds.data = [64, 32, 0, 128].repeat(16);
+
+        let options = DdsOptions {
+            unpremultiply_alpha: true,
+            ..Default::default()
+        };
+        let nutexb = NutexbFile::from_dds_with_options(&dds, "tex", options).unwrap();
+        assert_eq!(
+            [128, 64, 0, 128].repeat(16),
+            nutexb.deswizzled_data().unwrap()
+        );
+
+        let nutexb = NutexbFile::from_dds(&dds, "tex").unwrap();
+        assert_eq!(
+            [64, 32, 0, 128].repeat(16),
+            nutexb.deswizzled_data().unwrap()
+        );
+    }
+
+    #[cfg(not(feature = "encoder"))]
+    #[test]
+    fn from_dds_dxt2_unpremultiply_alpha() {
+        let options = DdsOptions {
+            unpremultiply_alpha: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            NutexbFile::from_dds_with_options(&d3d_dds(D3DFormat::DXT2), "tex", options),
+            Err(ReadDdsError::UnsupportedUnpremultiply(
+                NutexbFormat::BC2Unorm
+            ))
+        ));
+    }
+
+    #[cfg(feature = "encoder")]
+    fn check_unpremultiply_compressed(format: D3DFormat, image_format: image_dds::ImageFormat) {
+        use image_dds::{Mipmaps, Quality, SurfaceRgba8};
+
+        // 8x8 pixels with 4 mipmaps and a different color for each 4x4 block.
+        let premultiplied = [
+            [64, 32, 0, 128],
+            [0, 0, 0, 0],
+            [51, 51, 51, 51],
+            [0, 0, 0, 255],
+        ];
+        let data: Vec<u8> = (0..64)
+            .flat_map(|i| premultiplied[(i % 8 / 4) + (i / 32) * 2])
+            .collect();
+        let encoded = SurfaceRgba8 {
+            width: 8,
+            height: 8,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            data,
+        }
+        .encode(image_format, Quality::Normal, Mipmaps::GeneratedExact(4))
+        .unwrap();
+
+        // Compare with the decoded input to ignore differences from the initial compression.
+        let decode = |data: &[u8]| -> Vec<u8> {
+            image_dds::Surface {
+                width: 8,
+                height: 8,
+                depth: 1,
+                layers: 1,
+                mipmaps: 1,
+                image_format,
+                data,
+            }
+            .decode_rgba8()
+            .unwrap()
+            .data
+        };
+        let expected =
+            unpremultiply_alpha(NutexbFormat::R8G8B8A8Unorm, &decode(&encoded.data)).unwrap();
+
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format,
+            mipmap_levels: Some(4),
+            caps2: None,
+        })
+        .unwrap();
+        dds.data = encoded.data.clone();
+
+        let options = DdsOptions {
+            unpremultiply_alpha: true,
+            ..Default::default()
+        };
+        let nutexb = NutexbFile::from_dds_with_options(&dds, "tex", options).unwrap();
+        assert_eq!(4, nutexb.footer.mipmap_count);
+
+        let actual = decode(&nutexb.deswizzled_data().unwrap());
+        assert_ne!(decode(&encoded.data), actual);
+        for (actual, expected) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+            let matches = actual
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| a.abs_diff(*b) <= 4);
+            assert!(matches, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn from_dds_dxt2_unpremultiply_alpha() {
+        check_unpremultiply_compressed(D3DFormat::DXT2, image_dds::ImageFormat::BC2RgbaUnorm);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn from_dds_dxt4_unpremultiply_alpha() {
+        check_unpremultiply_compressed(D3DFormat::DXT4, image_dds::ImageFormat::BC3RgbaUnorm);
+    }
+
+    #[test]
+    fn create_surface_mipmap_count_too_high() {
+        let mut dds = dxgi_dds(DxgiFormat::R8G8B8A8_UNorm, AlphaMode::Unknown);
+        dds.header.mip_map_count = Some(40);
+        dds.data = vec![0u8; 4 * 4 * 4];
+
+        let options = DdsOptions {
+            allow_truncated_mipmaps: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            create_surface(&dds, options),
+            Err(ReadDdsError::SwizzleError(
+                tegra_swizzle::SwizzleError::InvalidSurface {
+                    mipmap_count: 40,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn create_surface_truncated_mipmaps() {
+        let mut dds = dxgi_dds(DxgiFormat::R8G8B8A8_UNorm, AlphaMode::Unknown);
+        dds.header.mip_map_count = Some(3);
+        // Include part of the third mipmap.
+        dds.data = vec![0u8; (4 * 4 + 2 * 2) * 4 + 1];
+
+        assert!(matches!(
+            create_surface(&dds, DdsOptions::default()),
+            Err(ReadDdsError::NotEnoughData {
+                expected_size: 84,
+                actual_size: 81
+            })
+        ));
+
+        let options = DdsOptions {
+            allow_truncated_mipmaps: true,
+            ..Default::default()
+        };
+        assert_eq!(2, create_surface(&dds, options).unwrap().mipmap_count);
+    }
+
+    #[test]
+    fn bc7_alpha_mode() {
+        // The mode is the index of the lowest set bit in the first byte.
+        let mode1 = [0b0000_0010; 16];
+        assert_eq!(
+            AlphaMode::Opaque,
+            detect_alpha_mode(NutexbFormat::BC7Unorm, &mode1)
+        );
+
+        let mode6 = [0b0100_0000; 16];
+        let data = [mode1, mode6].concat();
+        assert_eq!(
+            AlphaMode::Unknown,
+            detect_alpha_mode(NutexbFormat::BC7Srgb, &data)
+        );
+    }
+}
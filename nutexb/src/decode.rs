@@ -0,0 +1,142 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{NutexbFile, NutexbFormat, SurfaceError};
+
+/// Errors while decoding nutexb image data to RGBA.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The image format does not have a supported decoder.
+    UnsupportedFormat(NutexbFormat),
+    /// The image data could not be deswizzled.
+    SwizzleError(tegra_swizzle::SwizzleError),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnsupportedFormat(format) => {
+                write!(f, "decoding {format:?} to RGBA is not supported")
+            }
+            DecodeError::SwizzleError(e) => write!(f, "failed to deswizzle surface: {e}"),
+        }
+    }
+}
+
+impl From<tegra_swizzle::SwizzleError> for DecodeError {
+    fn from(value: tegra_swizzle::SwizzleError) -> Self {
+        Self::SwizzleError(value)
+    }
+}
+
+impl Error for DecodeError {}
+
+pub fn decode_rgba8(nutexb: &NutexbFile) -> Result<Vec<u8>, DecodeError> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    let data = match nutexb.deswizzled_data() {
+        Ok(data) => data,
+        Err(SurfaceError::SwizzleError(e)) => return Err(DecodeError::SwizzleError(e)),
+        // Deswizzling only fails otherwise for unknown formats.
+        Err(_) => return Err(DecodeError::UnsupportedFormat(format)),
+    };
+
+    match format {
+        NutexbFormat::R8Unorm => Ok(data.iter().flat_map(|r| [*r, 0, 0, 255]).collect()),
+        NutexbFormat::R8G8B8A8Unorm | NutexbFormat::R8G8B8A8Srgb => Ok(data),
+        NutexbFormat::B8G8R8A8Unorm | NutexbFormat::B8G8R8A8Srgb => Ok(data
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect()),
+        NutexbFormat::R32G32B32A32Float => Ok(data
+            .chunks_exact(4)
+            .map(|c| {
+                let value = f32::from_le_bytes(c.try_into().unwrap());
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect()),
+        NutexbFormat::R16G16B16A16Float => Ok(data
+            .chunks_exact(2)
+            .map(|c| unorm8(f16_to_f32([c[0], c[1]])))
+            .collect()),
+        NutexbFormat::BC1Unorm
+        | NutexbFormat::BC1Srgb
+        | NutexbFormat::BC2Unorm
+        | NutexbFormat::BC2Srgb
+        | NutexbFormat::BC3Unorm
+        | NutexbFormat::BC3Srgb
+        | NutexbFormat::BC4Unorm
+        | NutexbFormat::BC4Snorm
+        | NutexbFormat::BC5Unorm
+        | NutexbFormat::BC5Snorm
+        | NutexbFormat::BC6Ufloat
+        | NutexbFormat::BC6Sfloat
+        | NutexbFormat::BC7Unorm
+        | NutexbFormat::BC7Srgb
+        | NutexbFormat::Unknown(_) => Err(DecodeError::UnsupportedFormat(format)),
+    }
+}
+
+pub(crate) fn unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(any(feature = "ddsfile", feature = "image"))]
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(any(feature = "ddsfile", feature = "image"))]
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The equivalent image_dds format for block compressed formats.
+#[cfg(feature = "encoder")]
+pub(crate) fn bc_image_format(format: NutexbFormat) -> Option<image_dds::ImageFormat> {
+    use image_dds::ImageFormat;
+
+    match format {
+        NutexbFormat::BC1Unorm => Some(ImageFormat::BC1RgbaUnorm),
+        NutexbFormat::BC1Srgb => Some(ImageFormat::BC1RgbaUnormSrgb),
+        NutexbFormat::BC2Unorm => Some(ImageFormat::BC2RgbaUnorm),
+        NutexbFormat::BC2Srgb => Some(ImageFormat::BC2RgbaUnormSrgb),
+        NutexbFormat::BC3Unorm => Some(ImageFormat::BC3RgbaUnorm),
+        NutexbFormat::BC3Srgb => Some(ImageFormat::BC3RgbaUnormSrgb),
+        NutexbFormat::BC4Unorm => Some(ImageFormat::BC4RUnorm),
+        NutexbFormat::BC4Snorm => Some(ImageFormat::BC4RSnorm),
+        NutexbFormat::BC5Unorm => Some(ImageFormat::BC5RgUnorm),
+        NutexbFormat::BC5Snorm => Some(ImageFormat::BC5RgSnorm),
+        NutexbFormat::BC6Ufloat => Some(ImageFormat::BC6hRgbUfloat),
+        NutexbFormat::BC6Sfloat => Some(ImageFormat::BC6hRgbSfloat),
+        NutexbFormat::BC7Unorm => Some(ImageFormat::BC7RgbaUnorm),
+        NutexbFormat::BC7Srgb => Some(ImageFormat::BC7RgbaUnormSrgb),
+        _ => None,
+    }
+}
+
+/// Converts the little endian bytes of an IEEE half precision float to `f32`.
+pub(crate) fn f16_to_f32(bytes: [u8; 2]) -> f32 {
+    let bits = u16::from_le_bytes(bytes) as u32;
+    let sign = (bits >> 15) << 31;
+    let exponent = (bits >> 10) & 0x1F;
+    let mantissa = bits & 0x3FF;
+
+    let value = match exponent {
+        // Subnormal values are scaled by 2^-24.
+        0 => {
+            let magnitude = mantissa as f32 / 16777216.0;
+            return if sign != 0 { -magnitude } else { magnitude };
+        }
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(value)
+}
@@ -0,0 +1,157 @@
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+static TEMP_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Writes `bytes` to a temporary file in the same directory as `path` and renames it over `path`.
+/// This avoids leaving a truncated file at `path` if the process exits while writing.
+///
+/// If `keep_backup` is `true`, any existing file at `path` is first copied to `path` with `".bak"` appended.
+pub fn write_file_atomic(path: &Path, bytes: &[u8], keep_backup: bool) -> std::io::Result<()> {
+    let (temp_path, mut temp_file) = create_temp_file(path)?;
+
+    // Preserve the permissions of the file being replaced.
+    if let Ok(metadata) = std::fs::metadata(path) {
+        let _ = temp_file.set_permissions(metadata.permissions());
+    }
+
+    let result = temp_file
+        .write_all(bytes)
+        .and_then(|_| temp_file.sync_all())
+        .and_then(|_| {
+            // Copy instead of renaming so the target path always contains a complete file.
+            if keep_backup && path.exists() {
+                std::fs::copy(path, backup_path(path))?;
+            }
+            std::fs::rename(&temp_path, path)
+        });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // Persist the rename itself on platforms that support syncing directories.
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(parent_dir(path)) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// The path with `".bak"` appended like `"def_001_col.nutexb.bak"`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = OsString::from(path.as_os_str());
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn create_temp_file(path: &Path) -> std::io::Result<(PathBuf, File)> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{path:?} does not have a file name"),
+        )
+    })?;
+
+    // The temp file must be on the same file system for the rename to be atomic.
+    loop {
+        let mut temp_name = OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = parent_dir(path).join(temp_name);
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nutexb_fs_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<OsString> {
+        let mut names: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_new_file() {
+        let dir = test_dir("new");
+        let path = dir.join("a.nutexb");
+
+        write_file_atomic(&path, b"new", true).unwrap();
+        assert_eq!(b"new", std::fs::read(&path).unwrap().as_slice());
+        assert_eq!(vec![OsString::from("a.nutexb")], file_names(&dir));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn write_with_backup() {
+        let dir = test_dir("backup");
+        let path = dir.join("a.nutexb");
+        std::fs::write(&path, b"old").unwrap();
+
+        write_file_atomic(&path, b"new", true).unwrap();
+        assert_eq!(b"new", std::fs::read(&path).unwrap().as_slice());
+        assert_eq!(
+            b"old",
+            std::fs::read(dir.join("a.nutexb.bak")).unwrap().as_slice()
+        );
+        assert_eq!(
+            vec![OsString::from("a.nutexb"), OsString::from("a.nutexb.bak")],
+            file_names(&dir)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn write_without_backup() {
+        let dir = test_dir("no_backup");
+        let path = dir.join("a.nutexb");
+        std::fs::write(&path, b"old").unwrap();
+
+        write_file_atomic(&path, b"new", false).unwrap();
+        assert_eq!(b"new", std::fs::read(&path).unwrap().as_slice());
+        assert_eq!(vec![OsString::from("a.nutexb")], file_names(&dir));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
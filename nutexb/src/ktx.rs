@@ -0,0 +1,728 @@
+use std::{error::Error, fmt::Display, num::NonZeroU8};
+
+use ktx2::{
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, DfdBlockHeaderBasic,
+    DfdHeader, Format, Header, Index, LevelIndex, Reader, SampleInformation, TransferFunction,
+};
+
+use crate::{
+    convert::{is_valid_surface, mip_size},
+    NutexbFile, NutexbFormat, Surface, SurfaceError, TextureNameError,
+};
+
+/// Errors while creating a nutexb file from a KTX2 file.
+#[derive(Debug)]
+pub enum ReadKtx2Error {
+    /// The KTX2 format is not a recognized or supported nutexb format.
+    UnrecognizedFormat,
+    /// The KTX2 data uses supercompression like Zstandard or BasisLZ.
+    UnsupportedSupercompression,
+    /// A mipmap level is smaller than the size required by the dimensions, format, and layers in the header.
+    NotEnoughData {
+        mipmap: u32,
+        expected_size: usize,
+        actual_size: usize,
+    },
+    /// The total number of layers for the array layers and cube map faces does not fit in a `u32`.
+    InvalidLayerCount { layer_count: u32, face_count: u32 },
+    /// The KTX2 data could not be swizzled.
+    SwizzleError(tegra_swizzle::SwizzleError),
+    /// The name does not fit in the nutexb footer.
+    InvalidName(TextureNameError),
+}
+
+impl Display for ReadKtx2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadKtx2Error::UnrecognizedFormat => write!(f, "unrecognized KTX2 format"),
+            ReadKtx2Error::UnsupportedSupercompression => {
+                write!(f, "supercompressed KTX2 data is not supported")
+            }
+            ReadKtx2Error::NotEnoughData {
+                mipmap,
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "KTX2 mipmap {mipmap} expects {expected_size} bytes of image data but found {actual_size} bytes"
+            ),
+            ReadKtx2Error::InvalidLayerCount {
+                layer_count,
+                face_count,
+            } => write!(
+                f,
+                "layer count {layer_count} with {face_count} faces exceeds the maximum layer count"
+            ),
+            ReadKtx2Error::SwizzleError(e) => write!(f, "failed to swizzle surface: {e}"),
+            ReadKtx2Error::InvalidName(e) => write!(f, "invalid name: {e}"),
+        }
+    }
+}
+
+impl From<tegra_swizzle::SwizzleError> for ReadKtx2Error {
+    fn from(value: tegra_swizzle::SwizzleError) -> Self {
+        Self::SwizzleError(value)
+    }
+}
+
+impl From<SurfaceError> for ReadKtx2Error {
+    fn from(value: SurfaceError) -> Self {
+        match value {
+            SurfaceError::UnsupportedFormat(_) => Self::UnrecognizedFormat,
+            SurfaceError::SwizzleError(e) => Self::SwizzleError(e),
+            SurfaceError::InvalidName(e) => Self::InvalidName(e),
+            // Conversions don't write to caller provided buffers or copy linear data.
+            SurfaceError::BufferTooSmall {
+                expected_size,
+                actual_size,
+            }
+            | SurfaceError::DataSizeMismatch {
+                expected_size,
+                actual_size,
+            } => Self::SwizzleError(tegra_swizzle::SwizzleError::NotEnoughData {
+                expected_size,
+                actual_size,
+            }),
+        }
+    }
+}
+
+impl Error for ReadKtx2Error {}
+
+pub fn create_surface<D: AsRef<[u8]>>(ktx2: &Reader<D>) -> Result<Surface<Vec<u8>>, ReadKtx2Error> {
+    let header = ktx2.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(ReadKtx2Error::UnsupportedSupercompression);
+    }
+
+    let image_format = header
+        .format
+        .and_then(image_format_from_vk)
+        .ok_or(ReadKtx2Error::UnrecognizedFormat)?;
+
+    // KTX2 uses 0 to indicate dimensions, layers, or mipmaps that aren't present.
+    let width = header.pixel_width;
+    let height = header.pixel_height.max(1);
+    let depth = header.pixel_depth.max(1);
+    let mipmap_count = header.level_count.max(1);
+    let layer_count = header
+        .layer_count
+        .max(1)
+        .checked_mul(header.face_count.max(1))
+        .ok_or(ReadKtx2Error::InvalidLayerCount {
+            layer_count: header.layer_count,
+            face_count: header.face_count,
+        })?;
+
+    let block_dim = image_format.block_dim();
+    let bytes_per_pixel = image_format.bytes_per_pixel();
+
+    // Check the header before calculating any sizes to avoid overflow.
+    if !is_valid_surface(width, height, depth, bytes_per_pixel, mipmap_count) {
+        return Err(ReadKtx2Error::SwizzleError(
+            tegra_swizzle::SwizzleError::InvalidSurface {
+                width,
+                height,
+                depth,
+                bytes_per_pixel,
+                mipmap_count,
+            },
+        ));
+    }
+
+    // KTX2 groups data by mipmap instead of by layer.
+    // Faces and array layers for a level use the same order as nutexb layers.
+    let levels: Vec<_> = ktx2.levels().map(|level| level.data).collect();
+    for (mip, level) in levels.iter().enumerate() {
+        let expected_size = mip_size(width, height, depth, block_dim, bytes_per_pixel, mip as u32)
+            * layer_count as usize;
+        if level.len() < expected_size {
+            return Err(ReadKtx2Error::NotEnoughData {
+                mipmap: mip as u32,
+                expected_size,
+                actual_size: level.len(),
+            });
+        }
+    }
+
+    let mut image_data = Vec::new();
+    for layer in 0..layer_count as usize {
+        for (mip, level) in levels.iter().enumerate() {
+            let size = mip_size(width, height, depth, block_dim, bytes_per_pixel, mip as u32);
+            image_data.extend_from_slice(&level[layer * size..(layer + 1) * size]);
+        }
+    }
+
+    Ok(Surface {
+        width,
+        height,
+        depth,
+        image_data,
+        mipmap_count,
+        layer_count,
+        image_format,
+    })
+}
+
+fn image_format_from_vk(format: Format) -> Option<NutexbFormat> {
+    match format {
+        Format::R8_UNORM => Some(NutexbFormat::R8Unorm),
+        Format::R8G8B8A8_UNORM => Some(NutexbFormat::R8G8B8A8Unorm),
+        Format::R8G8B8A8_SRGB => Some(NutexbFormat::R8G8B8A8Srgb),
+        Format::R32G32B32A32_SFLOAT => Some(NutexbFormat::R32G32B32A32Float),
+        Format::B8G8R8A8_UNORM => Some(NutexbFormat::B8G8R8A8Unorm),
+        Format::B8G8R8A8_SRGB => Some(NutexbFormat::B8G8R8A8Srgb),
+        Format::R16G16B16A16_SFLOAT => Some(NutexbFormat::R16G16B16A16Float),
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => Some(NutexbFormat::BC1Unorm),
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => Some(NutexbFormat::BC1Srgb),
+        Format::BC2_UNORM_BLOCK => Some(NutexbFormat::BC2Unorm),
+        Format::BC2_SRGB_BLOCK => Some(NutexbFormat::BC2Srgb),
+        Format::BC3_UNORM_BLOCK => Some(NutexbFormat::BC3Unorm),
+        Format::BC3_SRGB_BLOCK => Some(NutexbFormat::BC3Srgb),
+        Format::BC4_UNORM_BLOCK => Some(NutexbFormat::BC4Unorm),
+        Format::BC4_SNORM_BLOCK => Some(NutexbFormat::BC4Snorm),
+        Format::BC5_UNORM_BLOCK => Some(NutexbFormat::BC5Unorm),
+        Format::BC5_SNORM_BLOCK => Some(NutexbFormat::BC5Snorm),
+        Format::BC6H_UFLOAT_BLOCK => Some(NutexbFormat::BC6Ufloat),
+        Format::BC6H_SFLOAT_BLOCK => Some(NutexbFormat::BC6Sfloat),
+        Format::BC7_UNORM_BLOCK => Some(NutexbFormat::BC7Unorm),
+        Format::BC7_SRGB_BLOCK => Some(NutexbFormat::BC7Srgb),
+        _ => None,
+    }
+}
+
+impl TryFrom<NutexbFormat> for Format {
+    type Error = SurfaceError;
+
+    fn try_from(value: NutexbFormat) -> Result<Self, Self::Error> {
+        Ok(match value {
+            NutexbFormat::R8Unorm => Self::R8_UNORM,
+            NutexbFormat::R8G8B8A8Unorm => Self::R8G8B8A8_UNORM,
+            NutexbFormat::R8G8B8A8Srgb => Self::R8G8B8A8_SRGB,
+            NutexbFormat::R32G32B32A32Float => Self::R32G32B32A32_SFLOAT,
+            NutexbFormat::B8G8R8A8Unorm => Self::B8G8R8A8_UNORM,
+            NutexbFormat::B8G8R8A8Srgb => Self::B8G8R8A8_SRGB,
+            NutexbFormat::R16G16B16A16Float => Self::R16G16B16A16_SFLOAT,
+            // Vulkan names packed formats from the most significant bits.
+            NutexbFormat::BC1Unorm => Self::BC1_RGBA_UNORM_BLOCK,
+            NutexbFormat::BC1Srgb => Self::BC1_RGBA_SRGB_BLOCK,
+            NutexbFormat::BC2Unorm => Self::BC2_UNORM_BLOCK,
+            NutexbFormat::BC2Srgb => Self::BC2_SRGB_BLOCK,
+            NutexbFormat::BC3Unorm => Self::BC3_UNORM_BLOCK,
+            NutexbFormat::BC3Srgb => Self::BC3_SRGB_BLOCK,
+            NutexbFormat::BC4Unorm => Self::BC4_UNORM_BLOCK,
+            NutexbFormat::BC4Snorm => Self::BC4_SNORM_BLOCK,
+            NutexbFormat::BC5Unorm => Self::BC5_UNORM_BLOCK,
+            NutexbFormat::BC5Snorm => Self::BC5_SNORM_BLOCK,
+            NutexbFormat::BC6Ufloat => Self::BC6H_UFLOAT_BLOCK,
+            NutexbFormat::BC6Sfloat => Self::BC6H_SFLOAT_BLOCK,
+            NutexbFormat::BC7Unorm => Self::BC7_UNORM_BLOCK,
+            NutexbFormat::BC7Srgb => Self::BC7_SRGB_BLOCK,
+            NutexbFormat::Unknown(_) => return Err(SurfaceError::UnsupportedFormat(value)),
+        })
+    }
+}
+
+pub fn create_ktx2(nutexb: &NutexbFile) -> Result<Vec<u8>, SurfaceError> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    let vk_format = Format::try_from(format)?;
+    let block_dim = format.block_dim();
+    let bytes_per_pixel = format.bytes_per_pixel();
+
+    let data = nutexb.deswizzled_data()?;
+
+    let mip_sizes: Vec<_> = (0..footer.mipmap_count)
+        .map(|mip| {
+            mip_size(
+                footer.width,
+                footer.height,
+                footer.depth,
+                block_dim,
+                bytes_per_pixel,
+                mip,
+            )
+        })
+        .collect();
+    let layer_size: usize = mip_sizes.iter().sum();
+
+    // Treat 6 layers as a cube map to match DDS.
+    let is_cubemap = footer.layer_count == 6;
+    let (layer_count, face_count) = if is_cubemap {
+        (0, 6)
+    } else if footer.layer_count > 1 {
+        (footer.layer_count, 1)
+    } else {
+        (0, 1)
+    };
+
+    let dfd = data_format_descriptor(format);
+
+    let level_index_offset = Header::LENGTH;
+    let dfd_offset = level_index_offset + LevelIndex::LENGTH * mip_sizes.len();
+
+    // Mipmaps are stored from smallest to largest with each level aligned to the block size.
+    let alignment = lcm(bytes_per_pixel as usize, 4);
+    let mut level_indices = vec![
+        LevelIndex {
+            byte_offset: 0,
+            byte_length: 0,
+            uncompressed_byte_length: 0
+        };
+        mip_sizes.len()
+    ];
+    let mut level_data = Vec::new();
+    let mut offset = dfd_offset + dfd.len();
+    for (mip, mip_size) in mip_sizes.iter().enumerate().rev() {
+        let padding = offset.next_multiple_of(alignment) - offset;
+        level_data.resize(level_data.len() + padding, 0);
+        offset += padding;
+
+        // Layers are stored contiguously for each mipmap level.
+        let mip_offset: usize = mip_sizes[..mip].iter().sum();
+        for layer in 0..footer.layer_count as usize {
+            let start = layer * layer_size + mip_offset;
+            level_data.extend_from_slice(&data[start..start + mip_size]);
+        }
+
+        let byte_length = (mip_size * footer.layer_count as usize) as u64;
+        level_indices[mip] = LevelIndex {
+            byte_offset: offset as u64,
+            byte_length,
+            uncompressed_byte_length: byte_length,
+        };
+        offset += byte_length as usize;
+    }
+
+    let header = Header {
+        format: Some(vk_format),
+        type_size: type_size(format),
+        pixel_width: footer.width,
+        pixel_height: footer.height,
+        pixel_depth: if footer.depth > 1 { footer.depth } else { 0 },
+        layer_count,
+        face_count,
+        level_count: footer.mipmap_count,
+        supercompression_scheme: None,
+        index: Index {
+            dfd_byte_offset: dfd_offset as u32,
+            dfd_byte_length: dfd.len() as u32,
+            kvd_byte_offset: 0,
+            kvd_byte_length: 0,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        },
+    };
+
+    let mut bytes = Vec::with_capacity(offset);
+    bytes.extend_from_slice(&header.as_bytes());
+    for level_index in &level_indices {
+        bytes.extend_from_slice(&level_index.as_bytes());
+    }
+    bytes.extend_from_slice(&dfd);
+    bytes.extend_from_slice(&level_data);
+    Ok(bytes)
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+fn type_size(format: NutexbFormat) -> u32 {
+    match format {
+        NutexbFormat::R32G32B32A32Float => 4,
+        NutexbFormat::R16G16B16A16Float => 2,
+        // Block compressed and 8-bit formats use a size of 1.
+        _ => 1,
+    }
+}
+
+// Channel IDs from the Khronos Data Format Specification.
+const CHANNEL_R: u8 = 0;
+const CHANNEL_G: u8 = 1;
+const CHANNEL_B: u8 = 2;
+const CHANNEL_A: u8 = 15;
+const CHANNEL_BC1A_ALPHA_PRESENT: u8 = 1;
+
+fn data_format_descriptor(format: NutexbFormat) -> Vec<u8> {
+    let (color_model, samples) = dfd_samples(format);
+
+    let is_srgb = format.is_srgb();
+
+    let dimension = |x: u32| NonZeroU8::new(x as u8).unwrap();
+    let block_header = DfdBlockHeaderBasic {
+        color_model: Some(color_model),
+        color_primaries: Some(ColorPrimaries::BT709),
+        transfer_function: Some(if is_srgb {
+            TransferFunction::SRGB
+        } else {
+            TransferFunction::Linear
+        }),
+        flags: DataFormatFlags::STRAIGHT_ALPHA,
+        texel_block_dimensions: [
+            dimension(format.block_width()),
+            dimension(format.block_height()),
+            dimension(format.block_depth()),
+            dimension(1),
+        ],
+        bytes_planes: [format.bytes_per_pixel() as u8, 0, 0, 0, 0, 0, 0, 0],
+    };
+
+    let block_size =
+        DfdHeader::LENGTH + DfdBlockHeaderBasic::LENGTH + SampleInformation::LENGTH * samples.len();
+
+    let mut dfd = Vec::with_capacity(4 + block_size);
+    dfd.extend_from_slice(&(4 + block_size as u32).to_le_bytes());
+    dfd.extend_from_slice(&DfdHeader::BASIC.as_bytes(block_size as u16));
+    dfd.extend_from_slice(&block_header.as_bytes());
+    for sample in samples {
+        // The alpha channel is always linear even for sRGB formats.
+        let sample = if is_srgb && sample.channel_type == CHANNEL_A {
+            SampleInformation {
+                channel_type_qualifiers: sample.channel_type_qualifiers
+                    | ChannelTypeQualifiers::LINEAR,
+                ..sample
+            }
+        } else {
+            sample
+        };
+        dfd.extend_from_slice(&sample.as_bytes());
+    }
+    dfd
+}
+
+fn dfd_samples(format: NutexbFormat) -> (ColorModel, Vec<SampleInformation>) {
+    let unorm8 =
+        |channel, offset| sample(channel, offset, 8, ChannelTypeQualifiers::empty(), 0, 255);
+    let float32 = |channel, offset| {
+        sample(
+            channel,
+            offset,
+            32,
+            ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+            (-1.0f32).to_bits(),
+            1.0f32.to_bits(),
+        )
+    };
+    let float16 = |channel, offset| {
+        sample(
+            channel,
+            offset,
+            16,
+            ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+            (-1.0f32).to_bits(),
+            1.0f32.to_bits(),
+        )
+    };
+    let unorm = |channel, offset, bits| {
+        sample(
+            channel,
+            offset,
+            bits,
+            ChannelTypeQualifiers::empty(),
+            0,
+            u32::MAX,
+        )
+    };
+    let snorm = |channel, offset, bits| {
+        sample(
+            channel,
+            offset,
+            bits,
+            ChannelTypeQualifiers::SIGNED,
+            i32::MIN as u32,
+            i32::MAX as u32,
+        )
+    };
+
+    match format {
+        NutexbFormat::R8Unorm => (ColorModel::RGBSDA, vec![unorm8(CHANNEL_R, 0)]),
+        NutexbFormat::R8G8B8A8Unorm | NutexbFormat::R8G8B8A8Srgb => (
+            ColorModel::RGBSDA,
+            vec![
+                unorm8(CHANNEL_R, 0),
+                unorm8(CHANNEL_G, 8),
+                unorm8(CHANNEL_B, 16),
+                unorm8(CHANNEL_A, 24),
+            ],
+        ),
+        NutexbFormat::B8G8R8A8Unorm | NutexbFormat::B8G8R8A8Srgb => (
+            ColorModel::RGBSDA,
+            vec![
+                unorm8(CHANNEL_B, 0),
+                unorm8(CHANNEL_G, 8),
+                unorm8(CHANNEL_R, 16),
+                unorm8(CHANNEL_A, 24),
+            ],
+        ),
+        NutexbFormat::R32G32B32A32Float => (
+            ColorModel::RGBSDA,
+            vec![
+                float32(CHANNEL_R, 0),
+                float32(CHANNEL_G, 32),
+                float32(CHANNEL_B, 64),
+                float32(CHANNEL_A, 96),
+            ],
+        ),
+        NutexbFormat::R16G16B16A16Float => (
+            ColorModel::RGBSDA,
+            vec![
+                float16(CHANNEL_R, 0),
+                float16(CHANNEL_G, 16),
+                float16(CHANNEL_B, 32),
+                float16(CHANNEL_A, 48),
+            ],
+        ),
+        NutexbFormat::BC1Unorm | NutexbFormat::BC1Srgb => (
+            ColorModel::BC1A,
+            vec![unorm(CHANNEL_BC1A_ALPHA_PRESENT, 0, 64)],
+        ),
+        NutexbFormat::BC2Unorm | NutexbFormat::BC2Srgb => (
+            ColorModel::BC2,
+            vec![unorm(CHANNEL_A, 0, 64), unorm(CHANNEL_R, 64, 64)],
+        ),
+        NutexbFormat::BC3Unorm | NutexbFormat::BC3Srgb => (
+            ColorModel::BC3,
+            vec![unorm(CHANNEL_A, 0, 64), unorm(CHANNEL_R, 64, 64)],
+        ),
+        NutexbFormat::BC4Unorm => (ColorModel::BC4, vec![unorm(CHANNEL_R, 0, 64)]),
+        NutexbFormat::BC4Snorm => (ColorModel::BC4, vec![snorm(CHANNEL_R, 0, 64)]),
+        NutexbFormat::BC5Unorm => (
+            ColorModel::BC5,
+            vec![unorm(CHANNEL_R, 0, 64), unorm(CHANNEL_G, 64, 64)],
+        ),
+        NutexbFormat::BC5Snorm => (
+            ColorModel::BC5,
+            vec![snorm(CHANNEL_R, 0, 64), snorm(CHANNEL_G, 64, 64)],
+        ),
+        NutexbFormat::BC6Ufloat => (
+            ColorModel::BC6H,
+            vec![sample(
+                CHANNEL_R,
+                0,
+                128,
+                ChannelTypeQualifiers::FLOAT,
+                0,
+                1.0f32.to_bits(),
+            )],
+        ),
+        NutexbFormat::BC6Sfloat => (
+            ColorModel::BC6H,
+            vec![sample(
+                CHANNEL_R,
+                0,
+                128,
+                ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+                (-1.0f32).to_bits(),
+                1.0f32.to_bits(),
+            )],
+        ),
+        NutexbFormat::BC7Unorm | NutexbFormat::BC7Srgb => {
+            (ColorModel::BC7, vec![unorm(CHANNEL_R, 0, 128)])
+        }
+        // Unknown formats are rejected before creating the descriptor.
+        NutexbFormat::Unknown(_) => (ColorModel::RGBSDA, Vec::new()),
+    }
+}
+
+fn sample(
+    channel_type: u8,
+    bit_offset: u16,
+    bit_length: u8,
+    channel_type_qualifiers: ChannelTypeQualifiers,
+    lower: u32,
+    upper: u32,
+) -> SampleInformation {
+    SampleInformation {
+        bit_offset,
+        bit_length: NonZeroU8::new(bit_length).unwrap(),
+        channel_type,
+        channel_type_qualifiers,
+        sample_positions: [0; 4],
+        lower,
+        upper,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assembles the bytes of an uncompressed R8G8B8A8_UNORM KTX2 file field by field
+    /// following the layout of the KTX 2.0 specification and libktx
+    /// with the DFD, the orientation metadata, and mipmaps stored from smallest to largest.
+    fn rgba8_ktx2(
+        width: u32,
+        height: u32,
+        layer_count: u32,
+        face_count: u32,
+        levels: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut dfd = Vec::new();
+        dfd.extend_from_slice(&92u32.to_le_bytes()); // dfdTotalSize
+        dfd.extend_from_slice(&0u32.to_le_bytes()); // vendorId, descriptorType
+        dfd.extend_from_slice(&(2u32 | (88 << 16)).to_le_bytes()); // versionNumber, descriptorBlockSize
+        dfd.extend_from_slice(&[1, 1, 1, 0]); // KHR_DF_MODEL_RGBSDA, BT709, linear, straight alpha
+        dfd.extend_from_slice(&[0, 0, 0, 0]); // texelBlockDimension0-3 minus 1
+        dfd.extend_from_slice(&[4, 0, 0, 0, 0, 0, 0, 0]); // bytesPlane0-7
+        for (i, channel) in [0u8, 1, 2, 15].into_iter().enumerate() {
+            dfd.extend_from_slice(&(i as u16 * 8).to_le_bytes()); // bitOffset
+            dfd.extend_from_slice(&[7, channel]); // bitLength minus 1, channelType
+            dfd.extend_from_slice(&[0, 0, 0, 0]); // samplePosition0-3
+            dfd.extend_from_slice(&0u32.to_le_bytes()); // sampleLower
+            dfd.extend_from_slice(&255u32.to_le_bytes()); // sampleUpper
+        }
+
+        let mut kvd = Vec::new();
+        let entry = b"KTXorientation\0rd\0";
+        kvd.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        kvd.extend_from_slice(entry);
+        kvd.resize(kvd.len().next_multiple_of(4), 0);
+
+        let level_index_offset = 80;
+        let dfd_offset = level_index_offset + 24 * levels.len();
+        let kvd_offset = dfd_offset + dfd.len();
+        let data_offset = (kvd_offset + kvd.len()).next_multiple_of(4);
+
+        let mut level_index = vec![[0u64; 3]; levels.len()];
+        let mut data = Vec::new();
+        for (i, level) in levels.iter().enumerate().rev() {
+            level_index[i] = [
+                (data_offset + data.len()) as u64,
+                level.len() as u64,
+                level.len() as u64,
+            ];
+            data.extend_from_slice(level);
+        }
+
+        let mut bytes = vec![
+            0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+        ];
+        for value in [
+            37, // VK_FORMAT_R8G8B8A8_UNORM
+            1,
+            width,
+            height,
+            0,
+            layer_count,
+            face_count,
+            levels.len() as u32,
+            0,
+            dfd_offset as u32,
+            dfd.len() as u32,
+            kvd_offset as u32,
+            kvd.len() as u32,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0u8; 16]); // sgdByteOffset, sgdByteLength
+        for index in level_index {
+            for value in index {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&dfd);
+        bytes.extend_from_slice(&kvd);
+        bytes.resize(data_offset, 0);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    /// Fills each face of each mipmap with a different value.
+    fn cube_levels(width: u32, height: u32, mipmap_count: u32) -> Vec<Vec<u8>> {
+        (0..mipmap_count)
+            .map(|mip| {
+                let size = ((width >> mip).max(1) * (height >> mip).max(1) * 4) as usize;
+                (0..6u8)
+                    .flat_map(|face| vec![face * 16 + mip as u8; size])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cube_map_with_mipmaps() {
+        let levels = cube_levels(8, 8, 4);
+        let bytes = rgba8_ktx2(8, 8, 0, 6, &levels);
+        let ktx2 = Reader::new(&bytes).unwrap();
+
+        let nutexb = NutexbFile::from_ktx2(&ktx2, "cube").unwrap();
+        assert_eq!(6, nutexb.footer.layer_count);
+        assert_eq!(4, nutexb.footer.mipmap_count);
+        assert_eq!(NutexbFormat::R8G8B8A8Unorm, nutexb.footer.image_format);
+
+        // Nutexb groups data by layer instead of by mipmap.
+        let expected: Vec<u8> = (0..6u8)
+            .flat_map(|face| {
+                (0..4u8).flat_map(move |mip| {
+                    let size = ((8 >> mip) * (8 >> mip) * 4) as usize;
+                    vec![face * 16 + mip; size]
+                })
+            })
+            .collect();
+        assert_eq!(expected, nutexb.deswizzled_data().unwrap());
+    }
+
+    #[test]
+    fn array_with_mipmaps() {
+        let levels = vec![
+            [vec![1u8; 4 * 4 * 4], vec![2u8; 4 * 4 * 4]].concat(),
+            [vec![3u8; 2 * 2 * 4], vec![4u8; 2 * 2 * 4]].concat(),
+            [vec![5u8; 4], vec![6u8; 4]].concat(),
+        ];
+        let bytes = rgba8_ktx2(4, 4, 2, 1, &levels);
+        let ktx2 = Reader::new(&bytes).unwrap();
+
+        let nutexb = NutexbFile::from_ktx2(&ktx2, "array").unwrap();
+        assert_eq!(2, nutexb.footer.layer_count);
+        assert_eq!(3, nutexb.footer.mipmap_count);
+
+        let expected = [
+            vec![1u8; 4 * 4 * 4],
+            vec![3u8; 2 * 2 * 4],
+            vec![5u8; 4],
+            vec![2u8; 4 * 4 * 4],
+            vec![4u8; 2 * 2 * 4],
+            vec![6u8; 4],
+        ]
+        .concat();
+        assert_eq!(expected, nutexb.deswizzled_data().unwrap());
+    }
+
+    #[test]
+    fn level_count_too_high() {
+        let levels = vec![vec![0u8; 4]; 33];
+        let bytes = rgba8_ktx2(1, 1, 0, 1, &levels);
+        let ktx2 = Reader::new(&bytes).unwrap();
+
+        assert!(matches!(
+            NutexbFile::from_ktx2(&ktx2, "tex"),
+            Err(ReadKtx2Error::SwizzleError(
+                tegra_swizzle::SwizzleError::InvalidSurface {
+                    mipmap_count: 33,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn layer_count_overflow() {
+        let bytes = rgba8_ktx2(1, 1, 0x8000_0000, 6, &[vec![0u8; 4]]);
+        let ktx2 = Reader::new(&bytes).unwrap();
+
+        assert!(matches!(
+            NutexbFile::from_ktx2(&ktx2, "tex"),
+            Err(ReadKtx2Error::InvalidLayerCount {
+                layer_count: 0x8000_0000,
+                face_count: 6
+            })
+        ));
+    }
+}
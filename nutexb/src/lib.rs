@@ -12,6 +12,14 @@
 //! Read a [NutexbFile] with [NutexbFile::read] or [NutexbFile::read_from_file].
 //! The image data needs to be deswizzled first with [NutexbFile::deswizzled_data]
 //! to use with applications that expect a standard row-major memory layout.
+//! Async applications can use [NutexbFile::read_from_file_async] and [NutexbFile::write_to_file_async]
+//! when using the `"tokio"` feature.
+//!
+//! Enable the `"rayon"` feature to swizzle and deswizzle array layers and mipmaps in parallel.
+//! The output is identical to the default serial implementation.
+//!
+//! Enable the `"encoder"` feature to regenerate mipmaps for block compressed formats like BC7
+//! with [NutexbFile::regenerate_mipmaps].
 /*!
 ```rust no_run
 # fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,6 +34,8 @@ let surface_data = nutexb.deswizzled_data();
 //! ## Writing
 //! The easiest way to create a [NutexbFile] is by calling [NutexbFile::from_dds] and
 //! [NutexbFile::from_image] when using the `"ddsfile"` and `"image"` features, respectively.
+//! KTX2 files are supported with [NutexbFile::from_ktx2] when using the `"ktx2"` feature.
+//! Textures from BNTX files can be converted with [NutexbFile::from_bntx_texture].
 //! For manually specifying the surface dimensions and data, use [NutexbFile::from_surface].
 /*!
 ```rust no_run
@@ -51,20 +61,27 @@ nutexb.write_to_file("col_001.nutexb")?;
 # Ok(()) }
 ```
  */
-use binrw::{binrw, prelude::*, Endian, NullString, VecArgs};
-use convert::{create_nutexb, create_nutexb_unswizzled};
+use binrw::{binrw, prelude::*, Endian, VecArgs};
+use convert::{
+    check_format, create_nutexb, create_nutexb_from_linear, create_nutexb_into,
+    create_nutexb_unswizzled, create_nutexb_with_name, create_nutexb_with_options,
+    is_valid_surface,
+};
 use std::{
+    fmt::Display,
     io::{Cursor, Read, Seek, SeekFrom, Write},
     num::NonZeroU32,
     path::Path,
+    str::FromStr,
 };
+use swizzle::SurfaceLayout;
 use tegra_swizzle::surface::{deswizzled_surface_size, swizzled_surface_size, BlockDim};
 
 #[cfg(feature = "ddsfile")]
 pub use ddsfile;
 
 #[cfg(feature = "ddsfile")]
-pub use dds::ReadDdsError;
+pub use dds::{DdsOptions, ReadDdsError};
 
 #[cfg(feature = "ddsfile")]
 mod dds;
@@ -72,8 +89,45 @@ mod dds;
 #[cfg(feature = "image")]
 pub use image;
 
+#[cfg(feature = "ktx2")]
+pub use ktx2;
+
+#[cfg(feature = "ktx2")]
+pub use ktx::ReadKtx2Error;
+
+#[cfg(feature = "ktx2")]
+mod ktx;
+
+#[cfg(feature = "tokio")]
+mod async_io;
+
 mod convert;
-pub use convert::Surface;
+pub use convert::{Surface, SurfaceError};
+
+mod fs;
+pub use fs::write_file_atomic;
+
+mod bntx;
+pub use bntx::{BntxFile, BntxTexture, ReadBntxError};
+
+mod decode;
+pub use decode::DecodeError;
+
+mod name;
+pub use name::{TextureName, TextureNameError};
+
+mod validate;
+pub use validate::{Severity, ValidationIssue};
+
+mod mipmaps;
+pub use mipmaps::MipmapError;
+
+mod region;
+pub use region::{Rect, RegionError};
+
+mod swizzle;
+pub use swizzle::SwizzleOptions;
+pub use tegra_swizzle::BlockHeight;
 
 const FOOTER_SIZE: usize = 112;
 const LAYER_MIPMAPS_SIZE: usize = 64;
@@ -94,31 +148,75 @@ pub struct NutexbFile {
     pub footer: NutexbFooter,
 }
 
+/// Determines how padding bytes are written by [NutexbFile::write_with_mode].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Write zeros for all padding like files created by this library.
+    #[default]
+    Canonical,
+    /// Write the padding bytes preserved when reading the file,
+    /// so reading and writing an unmodified file produces identical bytes.
+    RoundTrip,
+}
+
+/// Options for [NutexbFile::write_to_file_with_options].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteFileOptions {
+    /// Copy any existing file at the path to the path with `".bak"` appended before replacing it.
+    pub keep_backup: bool,
+    /// How padding bytes are written.
+    pub mode: WriteMode,
+}
+
+/// Limits applied when reading a [NutexbFile] from untrusted data.
+///
+/// The defaults accept all known nutexb files while rejecting footers
+/// that would otherwise cause excessive allocations.
+///
+/// # Examples
+/**
+```rust no_run
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use nutexb::{NutexbFile, ReadOptions};
+
+let options = ReadOptions {
+    max_data_size: 16 * 1024 * 1024,
+    ..Default::default()
+};
+let nutexb = NutexbFile::read_from_file_with_options("col_001.nutexb", options)?;
+# Ok(()) }
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// The maximum value for [layer_count](struct.NutexbFooter.html#structfield.layer_count).
+    pub max_layer_count: u32,
+    /// The maximum size in bytes of [data](struct.NutexbFile.html#structfield.data).
+    pub max_data_size: u64,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_layer_count: 2048,
+            max_data_size: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+// The mipmap sizes for each layer are padded to 64 bytes.
+const MAX_MIPMAP_COUNT: u32 = LAYER_MIPMAPS_SIZE as u32 / 4;
+
 // Use a custom parser since we don't know the data size until finding the footer.
 impl BinRead for NutexbFile {
-    type Args<'arg> = ();
+    type Args<'arg> = ReadOptions;
 
     fn read_options<R: Read + Seek>(
         reader: &mut R,
         _endian: Endian,
-        _args: Self::Args<'_>,
+        options: Self::Args<'_>,
     ) -> BinResult<Self> {
-        // We need the footer to know the size of the layer mipmaps.
-        reader.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
-        let footer: NutexbFooter = reader.read_le()?;
-
-        // We need the layer mipmaps to know the size of the data section.
-        reader.seek(SeekFrom::Current(
-            -(FOOTER_SIZE as i64 + LAYER_MIPMAPS_SIZE as i64 * footer.layer_count as i64),
-        ))?;
-
-        // The image data takes up the remaining space.
-        let data_size = reader.stream_position()?;
-
-        let layer_mipmaps: Vec<LayerMipmaps> = reader.read_le_args(VecArgs {
-            count: footer.layer_count as usize,
-            inner: (footer.mipmap_count,),
-        })?;
+        let (data_size, layer_mipmaps, footer) = read_layout(reader, options)?;
 
         reader.seek(SeekFrom::Start(0))?;
 
@@ -131,85 +229,1490 @@ impl BinRead for NutexbFile {
             footer,
         })
     }
-}
+}
+
+/// Reads the sections after the image data and returns the image data size.
+fn read_layout<R: Read + Seek>(
+    reader: &mut R,
+    options: ReadOptions,
+) -> BinResult<(u64, Vec<LayerMipmaps>, NutexbFooter)> {
+    let file_size = reader.seek(SeekFrom::End(0))?;
+
+    // We need the footer to know the size of the layer mipmaps.
+    let footer_pos = footer_pos(file_size)?;
+    reader.seek(SeekFrom::Start(footer_pos))?;
+    let footer: NutexbFooter = reader.read_le()?;
+
+    let data_size = check_footer(&footer, file_size, options)?;
+
+    reader.seek(SeekFrom::Start(data_size))?;
+    let layer_mipmaps: Vec<LayerMipmaps> = reader.read_le_args(VecArgs {
+        count: footer.layer_count as usize,
+        inner: (footer.mipmap_count,),
+    })?;
+
+    Ok((data_size, layer_mipmaps, footer))
+}
+
+/// The position of the footer at the end of a file with size `file_size`.
+fn footer_pos(file_size: u64) -> BinResult<u64> {
+    if file_size < FOOTER_SIZE as u64 {
+        return Err(binrw::Error::AssertFail {
+            pos: 0,
+            message: format!("file size {file_size} is smaller than the {FOOTER_SIZE} byte footer"),
+        });
+    }
+    Ok(file_size - FOOTER_SIZE as u64)
+}
+
+/// Checks the `footer` against the limits in `options` and returns the image data size.
+fn check_footer(footer: &NutexbFooter, file_size: u64, options: ReadOptions) -> BinResult<u64> {
+    let footer_pos = footer_pos(file_size)?;
+
+    if footer.mipmap_count > MAX_MIPMAP_COUNT {
+        return Err(binrw::Error::AssertFail {
+            pos: footer_pos,
+            message: format!(
+                "mipmap count {} exceeds the maximum of {MAX_MIPMAP_COUNT}",
+                footer.mipmap_count
+            ),
+        });
+    }
+
+    if footer.layer_count > options.max_layer_count {
+        return Err(binrw::Error::AssertFail {
+            pos: footer_pos,
+            message: format!(
+                "layer count {} exceeds the maximum of {}",
+                footer.layer_count, options.max_layer_count
+            ),
+        });
+    }
+
+    // We need the layer mipmaps to know the size of the data section.
+    let layer_mipmaps_size = LAYER_MIPMAPS_SIZE as u64 * footer.layer_count as u64;
+    let data_size =
+        footer_pos
+            .checked_sub(layer_mipmaps_size)
+            .ok_or_else(|| binrw::Error::AssertFail {
+                pos: footer_pos,
+                message: format!(
+                    "mipmap sizes for {} layers do not fit in the {file_size} byte file",
+                    footer.layer_count
+                ),
+            })?;
+
+    // The image data takes up the remaining space.
+    if data_size > options.max_data_size {
+        return Err(binrw::Error::AssertFail {
+            pos: 0,
+            message: format!(
+                "data size {data_size} exceeds the maximum of {}",
+                options.max_data_size
+            ),
+        });
+    }
+
+    Ok(data_size)
+}
+
+impl ReadOptions {
+    /// The maximum size in bytes of an entire file with the image data, mipmap sizes, and footer.
+    fn max_file_size(&self) -> u64 {
+        self.max_data_size.saturating_add(
+            FOOTER_SIZE as u64 + LAYER_MIPMAPS_SIZE as u64 * self.max_layer_count as u64,
+        )
+    }
+}
+
+fn check_file_size(file_size: u64, options: ReadOptions) -> BinResult<()> {
+    let max_file_size = options.max_file_size();
+    if file_size > max_file_size {
+        Err(binrw::Error::AssertFail {
+            pos: 0,
+            message: format!("file size {file_size} exceeds the maximum of {max_file_size}"),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+impl NutexbFile {
+    /// Reads the [NutexbFile] from the specified `reader` using the default [ReadOptions].
+    pub fn read<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
+        reader.read_le::<NutexbFile>()
+    }
+
+    /// Reads the [NutexbFile] from the specified `reader`
+    /// and returns an error for files that exceed the limits in `options`.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    use nutexb::{NutexbFile, ReadOptions};
+
+    // Truncated files return an error instead of panicking.
+    let mut reader = std::io::Cursor::new(vec![0u8; 64]);
+    assert!(NutexbFile::read_with_options(&mut reader, ReadOptions::default()).is_err());
+    ```
+    */
+    pub fn read_with_options<R: Read + Seek>(
+        reader: &mut R,
+        options: ReadOptions,
+    ) -> BinResult<Self> {
+        reader.read_le_args::<NutexbFile>(options)
+    }
+
+    /// Reads the [NutexbFile] from the specified `path` using the default [ReadOptions].
+    /// The entire file is buffered to improve performance.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<NutexbFile, binrw::Error> {
+        Self::read_from_file_with_options(path, ReadOptions::default())
+    }
+
+    /// Reads the [NutexbFile] from the specified `path`
+    /// and returns an error for files that exceed the limits in `options`.
+    /// The file size is checked before buffering the entire file.
+    pub fn read_from_file_with_options<P: AsRef<Path>>(
+        path: P,
+        options: ReadOptions,
+    ) -> Result<NutexbFile, binrw::Error> {
+        let path = path.as_ref();
+        let file_size = std::fs::metadata(path)?.len();
+        check_file_size(file_size, options)?;
+
+        Self::from_vec_with_options(std::fs::read(path)?, options)
+    }
+
+    /// Reads the [NutexbFile] from a `reader` that does not support seeking like a pipe or decompression stream
+    /// using the default [ReadOptions].
+    pub fn read_unseekable<R: Read>(reader: &mut R) -> BinResult<Self> {
+        Self::read_unseekable_with_options(reader, ReadOptions::default())
+    }
+
+    /// Reads the [NutexbFile] from a `reader` that does not support seeking like a pipe or decompression stream.
+    ///
+    /// The footer is at the end of the file, so the entire stream is buffered first.
+    /// Streams larger than the file size allowed by `options` return an error without reading the remaining data.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, ReadOptions, Surface};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut bytes = Vec::new();
+    NutexbFile::from_surface(surface, "tex")?.write_to(&mut bytes)?;
+
+    // &[u8] implements Read but not Seek.
+    let nutexb = NutexbFile::read_unseekable(&mut bytes.as_slice())?;
+    assert_eq!("tex", nutexb.footer.name());
+
+    let options = ReadOptions {
+        max_data_size: 16,
+        ..Default::default()
+    };
+    assert!(NutexbFile::read_unseekable_with_options(&mut bytes.as_slice(), options).is_err());
+    # Ok(()) }
+    ```
+    */
+    pub fn read_unseekable_with_options<R: Read>(
+        reader: &mut R,
+        options: ReadOptions,
+    ) -> BinResult<Self> {
+        // Read one byte past the limit to detect streams that are too large.
+        let mut bytes = Vec::new();
+        reader
+            .take(options.max_file_size().saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        check_file_size(bytes.len() as u64, options)?;
+
+        Self::from_vec_with_options(bytes, options)
+    }
+
+    /// Reads the [NutexbFile] from the bytes of an entire file using the default [ReadOptions].
+    pub fn from_bytes(bytes: &[u8]) -> BinResult<Self> {
+        let (data_size, layer_mipmaps, footer) =
+            read_layout(&mut Cursor::new(bytes), ReadOptions::default())?;
+
+        Ok(Self {
+            data: bytes[..data_size as usize].to_vec(),
+            layer_mipmaps,
+            footer,
+        })
+    }
+
+    /// Reads the [NutexbFile] from the bytes of an entire file using the default [ReadOptions].
+    /// The allocation for `bytes` is reused for [data](#structfield.data) without copying.
+    pub fn from_vec(bytes: Vec<u8>) -> BinResult<Self> {
+        Self::from_vec_with_options(bytes, ReadOptions::default())
+    }
+
+    fn from_vec_with_options(mut bytes: Vec<u8>, options: ReadOptions) -> BinResult<Self> {
+        let (data_size, layer_mipmaps, footer) = read_layout(&mut Cursor::new(&bytes), options)?;
+
+        // The image data is at the start of the file.
+        bytes.truncate(data_size as usize);
+
+        Ok(Self {
+            data: bytes,
+            layer_mipmaps,
+            footer,
+        })
+    }
+
+    /// Writes the [NutexbFile] to the specified `writer` using [WriteMode::Canonical].
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
+        self.write_le(writer)
+    }
+
+    /// Writes the [NutexbFile] to the specified `writer` like [NutexbFile::write] without seeking.
+    /// This supports streams like stdout, HTTP bodies, or archive entries.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    // Vec<u8> implements Write but not Seek.
+    let mut bytes = Vec::new();
+    nutexb.write_to(&mut bytes)?;
+    assert_eq!(nutexb.serialized_size(), bytes.len());
+
+    let mut writer = std::io::Cursor::new(Vec::new());
+    nutexb.write(&mut writer)?;
+    assert_eq!(writer.into_inner(), bytes);
+    # Ok(()) }
+    ```
+    */
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), binrw::Error> {
+        self.write_to_with_mode(writer, WriteMode::Canonical)
+    }
+
+    /// Writes the [NutexbFile] to the specified `writer` like [NutexbFile::write_with_mode] without seeking.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface, WriteMode};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut bytes = Vec::new();
+    NutexbFile::from_surface(surface, "tex")?.write_to(&mut bytes)?;
+
+    // Add garbage after the mipmap sizes and the null terminator of the name.
+    let layer_padding = bytes.len() - 112 - 64 + 4;
+    bytes[layer_padding] = 1;
+    let name_padding = bytes.len() - 112 + 4 + 4;
+    bytes[name_padding..name_padding + 4].copy_from_slice(b"_col");
+
+    let nutexb = NutexbFile::from_bytes(&bytes)?;
+
+    let mut output = Vec::new();
+    nutexb.write_to_with_mode(&mut output, WriteMode::RoundTrip)?;
+    assert_eq!(bytes, output);
+    # Ok(()) }
+    ```
+    */
+    pub fn write_to_with_mode<W: Write>(
+        &self,
+        writer: &mut W,
+        mode: WriteMode,
+    ) -> Result<(), binrw::Error> {
+        let tail = self.layer_mipmaps_and_footer_bytes(mode)?;
+
+        writer.write_all(&self.data)?;
+        writer.write_all(&tail)?;
+        Ok(())
+    }
+
+    fn layer_mipmaps_and_footer_bytes(&self, mode: WriteMode) -> BinResult<Vec<u8>> {
+        // The sections are in file order, so only the small mipmap table and footer need buffering.
+        // Serialize these first to avoid writing partial files on error.
+        let mut tail = Cursor::new(Vec::new());
+        self.layer_mipmaps.write_le(&mut tail)?;
+        self.footer.write_le(&mut tail)?;
+        let mut tail = tail.into_inner();
+
+        if mode == WriteMode::RoundTrip {
+            // Padding is only restored if it still fits after any modified values.
+            let mut layer_pos = 0;
+            for layer in &self.layer_mipmaps {
+                let sizes_size = layer.mipmap_sizes.len() * 4;
+                if sizes_size + layer.padding.len() == LAYER_MIPMAPS_SIZE {
+                    tail[layer_pos + sizes_size..layer_pos + LAYER_MIPMAPS_SIZE]
+                        .copy_from_slice(&layer.padding);
+                }
+                layer_pos += sizes_size.max(LAYER_MIPMAPS_SIZE);
+            }
+
+            let name_size = self.footer.name.as_bytes().len() + 1;
+            if name_size + self.footer.name_padding.len() == 0x40 {
+                // Skip the footer magic and null terminated name.
+                let name_pos = layer_pos + 4;
+                tail[name_pos + name_size..name_pos + 0x40]
+                    .copy_from_slice(&self.footer.name_padding);
+            }
+        }
+
+        Ok(tail)
+    }
+
+    /// The size in bytes written by [NutexbFile::write] or [NutexbFile::write_to] in any [WriteMode].
+    pub fn serialized_size(&self) -> usize {
+        let layer_mipmaps_size: usize = self
+            .layer_mipmaps
+            .iter()
+            .map(|l| (l.mipmap_sizes.len() * 4).max(LAYER_MIPMAPS_SIZE))
+            .sum();
+        self.data.len() + layer_mipmaps_size + FOOTER_SIZE
+    }
+
+    /// Writes the [NutexbFile] to the specified `writer` using the specified padding `mode`.
+    ///
+    /// # Examples
+    /// Unmodified files are written identically with [WriteMode::RoundTrip].
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface, WriteMode};
+    use std::io::Cursor;
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut writer = Cursor::new(Vec::new());
+    NutexbFile::from_surface(surface, "tex")?.write(&mut writer)?;
+    let mut bytes = writer.into_inner();
+
+    // Add garbage after the null terminator of the name.
+    let name_padding = bytes.len() - 112 + 4 + 4;
+    bytes[name_padding..name_padding + 4].copy_from_slice(b"_col");
+
+    let nutexb = NutexbFile::read(&mut Cursor::new(&bytes))?;
+
+    let mut writer = Cursor::new(Vec::new());
+    nutexb.write_with_mode(&mut writer, WriteMode::RoundTrip)?;
+    assert_eq!(bytes, writer.into_inner());
+
+    let mut writer = Cursor::new(Vec::new());
+    nutexb.write_with_mode(&mut writer, WriteMode::Canonical)?;
+    assert_ne!(bytes, writer.into_inner());
+    # Ok(()) }
+    ```
+    */
+    pub fn write_with_mode<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        mode: WriteMode,
+    ) -> Result<(), binrw::Error> {
+        self.write_to_with_mode(writer, mode)
+    }
+
+    /// Writes the [NutexbFile] to the specified `path` using the default [WriteFileOptions].
+    /// The entire file is buffered to improve performance.
+    ///
+    /// The data is written to a temporary file in the same directory that replaces `path` once complete,
+    /// so an interrupted write never leaves a truncated file at `path`.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), binrw::Error> {
+        self.write_to_file_with_options(path, WriteFileOptions::default())
+    }
+
+    /// Writes the [NutexbFile] to the specified `path` like [NutexbFile::write_to_file] using the specified `options`.
+    ///
+    /// # Examples
+    /**
+    ```rust no_run
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, WriteFileOptions};
+
+    let nutexb = NutexbFile::read_from_file("col_001.nutexb")?;
+
+    // Keep the previous file as "col_001.nutexb.bak".
+    let options = WriteFileOptions {
+        keep_backup: true,
+        ..Default::default()
+    };
+    nutexb.write_to_file_with_options("col_001.nutexb", options)?;
+    # Ok(()) }
+    ```
+    */
+    pub fn write_to_file_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteFileOptions,
+    ) -> Result<(), binrw::Error> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_with_mode(&mut writer, options.mode)?;
+        fs::write_file_atomic(path.as_ref(), writer.get_ref(), options.keep_backup)
+            .map_err(Into::into)
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Reads the [NutexbFile] from the specified `reader` like [NutexbFile::read] without blocking.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+    use std::io::Cursor;
+
+    # let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    # runtime.block_on(async {
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    let mut bytes = Vec::new();
+    nutexb.write_async(&mut bytes).await?;
+
+    let footer = NutexbFile::read_footer_async(&mut Cursor::new(&bytes)).await?;
+    assert_eq!("tex", footer.name());
+
+    let nutexb = NutexbFile::read_async(&mut Cursor::new(&bytes)).await?;
+    assert_eq!(4, nutexb.footer.width);
+    # Ok::<_, Box<dyn std::error::Error>>(())
+    # })?;
+    # Ok(()) }
+    ```
+    */
+    pub async fn read_async<R>(reader: &mut R) -> BinResult<Self>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        async_io::read(reader, ReadOptions::default()).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Reads the [NutexbFile] from the specified `reader` like [NutexbFile::read_with_options] without blocking.
+    pub async fn read_with_options_async<R>(reader: &mut R, options: ReadOptions) -> BinResult<Self>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        async_io::read(reader, options).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Reads only the [NutexbFooter] from the end of `reader` without blocking.
+    ///
+    /// This is much faster than reading the entire file when only the dimensions, format, or name are needed.
+    /// The footer is checked against the default [ReadOptions] like [NutexbFile::read_async].
+    pub async fn read_footer_async<R>(reader: &mut R) -> BinResult<NutexbFooter>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        async_io::read_footer(reader, ReadOptions::default())
+            .await
+            .map(|(_, footer)| footer)
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Reads the [NutexbFile] from the specified `path` like [NutexbFile::read_from_file] without blocking.
+    ///
+    /// # Examples
+    /**
+    ```rust no_run
+    # async fn convert() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::NutexbFile;
+
+    let mut nutexb = NutexbFile::read_from_file_async("col_001.nutexb").await?;
+    nutexb.optimize_size();
+    nutexb.write_to_file_async("col_001.nutexb").await?;
+    # Ok(()) }
+    ```
+    */
+    pub async fn read_from_file_async<P: AsRef<Path>>(path: P) -> BinResult<Self> {
+        Self::read_from_file_with_options_async(path, ReadOptions::default()).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Reads the [NutexbFile] from the specified `path` like [NutexbFile::read_from_file_with_options] without blocking.
+    pub async fn read_from_file_with_options_async<P: AsRef<Path>>(
+        path: P,
+        options: ReadOptions,
+    ) -> BinResult<Self> {
+        async_io::read_from_file(path.as_ref().to_path_buf(), options).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Writes the [NutexbFile] to the specified `writer` like [NutexbFile::write_to] without blocking.
+    pub async fn write_async<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+    ) -> BinResult<()> {
+        async_io::write(self, writer, WriteMode::Canonical).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Writes the [NutexbFile] to the specified `writer` like [NutexbFile::write_to_with_mode] without blocking.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface, WriteMode};
+
+    # let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    # runtime.block_on(async {
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut bytes = Vec::new();
+    NutexbFile::from_surface(surface, "tex")?.write_async(&mut bytes).await?;
+
+    // Add garbage after the null terminator of the name.
+    let name_padding = bytes.len() - 112 + 4 + 4;
+    bytes[name_padding..name_padding + 4].copy_from_slice(b"_col");
+
+    let nutexb = NutexbFile::from_bytes(&bytes)?;
+
+    let mut output = Vec::new();
+    nutexb.write_with_mode_async(&mut output, WriteMode::RoundTrip).await?;
+    assert_eq!(bytes, output);
+    # Ok::<_, Box<dyn std::error::Error>>(())
+    # })?;
+    # Ok(()) }
+    ```
+    */
+    pub async fn write_with_mode_async<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        mode: WriteMode,
+    ) -> BinResult<()> {
+        async_io::write(self, writer, mode).await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Writes the [NutexbFile] to the specified `path` like [NutexbFile::write_to_file] without blocking.
+    ///
+    /// The file is replaced on tokio's blocking thread pool, so this must be called from within a tokio runtime.
+    pub async fn write_to_file_async<P: AsRef<Path>>(&self, path: P) -> BinResult<()> {
+        self.write_to_file_with_options_async(path, WriteFileOptions::default())
+            .await
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Writes the [NutexbFile] to the specified `path` like [NutexbFile::write_to_file_with_options] without blocking.
+    pub async fn write_to_file_with_options_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteFileOptions,
+    ) -> BinResult<()> {
+        async_io::write_to_file(self, path.as_ref().to_path_buf(), options).await
+    }
+
+    /// Deswizzles all the layers and mipmaps in [data](#structfield.data).
+    pub fn deswizzled_data(&self) -> Result<Vec<u8>, SurfaceError> {
+        self.deswizzled_data_with_options(SwizzleOptions::default())
+    }
+
+    /// Deswizzles all the layers and mipmaps in [data](#structfield.data) using the specified `options`.
+    /// See [NutexbFile::score_block_heights] for finding the block height for files that deswizzle incorrectly.
+    pub fn deswizzled_data_with_options(
+        &self,
+        options: SwizzleOptions,
+    ) -> Result<Vec<u8>, SurfaceError> {
+        check_format(self.footer.image_format)?;
+        swizzle::deswizzle_surface(self.footer.layout_with_options(options), &self.data)
+            .map_err(Into::into)
+    }
+
+    /// Scores each block height by how coherent the deswizzled image data is and returns the results sorted from best to worst.
+    /// Lower scores are better.
+    ///
+    /// Incorrect block heights scramble the rows of the image, so the score is the average difference
+    /// between the bytes of adjacent rows in the base mipmap of the first layer.
+    /// This works best for uncompressed formats and images with smooth regions.
+    /// The inferred block height is preferred for ties.
+    /// Block heights that would require more bytes than the length of [data](#structfield.data) are skipped.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{BlockHeight, NutexbFile, NutexbFormat, Surface, SwizzleOptions};
+
+    // Create a smooth vertical gradient using a non default block height.
+    let image_data: Vec<u8> = (0..128u32)
+        .flat_map(|y| [y as u8; 128 * 4])
+        .collect();
+    let surface = Surface {
+        width: 128,
+        height: 128,
+        depth: 1,
+        image_data: image_data.clone(),
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let options = SwizzleOptions {
+        block_height: Some(BlockHeight::Two),
+    };
+    let nutexb = NutexbFile::from_surface_with_options(surface, "tex", options)?;
+    assert_ne!(image_data, nutexb.deswizzled_data()?);
+
+    let (block_height, _) = nutexb.score_block_heights()?[0];
+    assert_eq!(BlockHeight::Two, block_height);
+
+    let options = SwizzleOptions {
+        block_height: Some(block_height),
+    };
+    assert_eq!(image_data, nutexb.deswizzled_data_with_options(options)?);
+    # Ok(()) }
+    ```
+    */
+    pub fn score_block_heights(&self) -> Result<Vec<(BlockHeight, f64)>, SurfaceError> {
+        check_format(self.footer.image_format)?;
+        Ok(swizzle::score_block_heights(
+            self.footer.layout(),
+            &self.data,
+        ))
+    }
+
+    /// The size in bytes of the result of [NutexbFile::deswizzled_data] calculated from the [footer](#structfield.footer).
+    pub fn required_deswizzled_size(&self) -> Result<usize, SurfaceError> {
+        check_format(self.footer.image_format)?;
+        self.footer.layout().deswizzled_size().map_err(Into::into)
+    }
+
+    /// Deswizzles all the layers and mipmaps in [data](#structfield.data) like [NutexbFile::deswizzled_data]
+    /// to the start of `output` and returns the number of bytes written.
+    ///
+    /// Returns [SurfaceError::BufferTooSmall] if `output` has fewer bytes than [NutexbFile::required_deswizzled_size].
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![1u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    // Reuse the same buffer for each conversion.
+    let mut buffer = vec![0u8; nutexb.required_deswizzled_size()?];
+    let size = nutexb.deswizzle_into(&mut buffer)?;
+    assert_eq!(nutexb.deswizzled_data()?, &buffer[..size]);
+
+    assert!(nutexb.deswizzle_into(&mut buffer[..16]).is_err());
+    # Ok(()) }
+    ```
+    */
+    pub fn deswizzle_into(&self, output: &mut [u8]) -> Result<usize, SurfaceError> {
+        let size = self.required_deswizzled_size()?;
+        if output.len() < size {
+            return Err(SurfaceError::BufferTooSmall {
+                expected_size: size,
+                actual_size: output.len(),
+            });
+        }
+
+        swizzle::deswizzle_surface_into(self.footer.layout(), &self.data, output)?;
+        Ok(size)
+    }
+
+    /// Calculates the offset in bytes in [data](#structfield.data) for the block at `x`, `y`, `z`
+    /// in mipmap `mip` of layer `layer` without deswizzling the surface.
+    /// Returns `None` if the format is unknown or the location is out of range for the [footer](#structfield.footer).
+    ///
+    /// The coordinates are in blocks, so divide pixel coordinates by the block dimensions
+    /// for compressed formats like BC7 with 4x4 pixel blocks.
+    /// Uncompressed formats have 1x1 pixel blocks and use pixel coordinates.
+    /// Each block has [NutexbFormat::bytes_per_pixel] many contiguous bytes starting at the offset.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    // Each pixel stores its layer, mipmap, and coordinates.
+    let mips = [(16u8, 0u8), (8, 1), (4, 2)];
+    let image_data: Vec<u8> = (0..2u8)
+        .flat_map(|layer| {
+            mips.iter().flat_map(move |&(size, mip)| {
+                (0..size).flat_map(move |y| (0..size).flat_map(move |x| [layer, mip, x, y]))
+            })
+        })
+        .collect();
+    let surface = Surface {
+        width: 16,
+        height: 16,
+        depth: 1,
+        image_data,
+        mipmap_count: 3,
+        layer_count: 2,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    let offset = nutexb.texel_offset(1, 1, 5, 3, 0).unwrap();
+    assert_eq!(&[1, 1, 5, 3], &nutexb.data[offset..offset + 4]);
+    assert_eq!(Some(&[0, 2, 3, 1][..]), nutexb.read_texel(0, 2, 3, 1, 0));
+
+    // Locations outside the surface have no offset.
+    assert_eq!(None, nutexb.texel_offset(2, 0, 0, 0, 0));
+    assert_eq!(None, nutexb.texel_offset(0, 2, 4, 0, 0));
+    # Ok(()) }
+    ```
+    */
+    pub fn texel_offset(&self, layer: u32, mip: u32, x: u32, y: u32, z: u32) -> Option<usize> {
+        check_format(self.footer.image_format).ok()?;
+
+        let mip = self.footer.layout().mip(layer, mip)?;
+        if !mip.contains(x, y, z) {
+            return None;
+        }
+
+        if self.footer.unk3 == 0x1000 {
+            Some(mip.swizzled_block_offset(x, y, z))
+        } else {
+            Some(mip.deswizzled_block_offset(x, y, z))
+        }
+    }
+
+    /// Returns the bytes for the block at block coordinates `x`, `y`, `z` in mipmap `mip` of layer `layer`
+    /// or `None` if the location is out of range or [data](#structfield.data) is too small.
+    /// See [NutexbFile::texel_offset] for details on the coordinates.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    // Each 4x4 pixel block of BC7 is 16 bytes.
+    let image_data: Vec<u8> = (0..16u8).flat_map(|block| [block; 16]).collect();
+    let surface = Surface {
+        width: 16,
+        height: 16,
+        depth: 1,
+        image_data,
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::BC7Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    // The pixel at (9, 6) is in the block at (2, 1).
+    assert_eq!(Some(&[6u8; 16][..]), nutexb.read_block(0, 0, 9 / 4, 6 / 4, 0));
+    # Ok(()) }
+    ```
+    */
+    pub fn read_block(&self, layer: u32, mip: u32, x: u32, y: u32, z: u32) -> Option<&[u8]> {
+        let offset = self.texel_offset(layer, mip, x, y, z)?;
+        let size = self.footer.image_format.bytes_per_pixel() as usize;
+        self.data.get(offset..offset + size)
+    }
+
+    /// Returns the bytes for the pixel at `x`, `y`, `z` in mipmap `mip` of layer `layer`
+    /// or `None` if the location is out of range or [data](#structfield.data) is too small.
+    ///
+    /// Pixels for compressed formats don't have their own bytes, so this always returns `None` for compressed formats.
+    /// Use [NutexbFile::read_block] to get the compressed block containing a pixel instead.
+    pub fn read_texel(&self, layer: u32, mip: u32, x: u32, y: u32, z: u32) -> Option<&[u8]> {
+        if self.footer.image_format.is_compressed() {
+            return None;
+        }
+        self.read_block(layer, mip, x, y, z)
+    }
+
+    /// Reads the blocks in `rect` for mipmap `mip` of layer `layer` without deswizzling the surface.
+    ///
+    /// The returned data stores the rows of blocks for each depth slice without any padding
+    /// like the data for a [Surface] with the dimensions of `rect`.
+    /// The `rect` is in pixels and must be aligned to the block dimensions for compressed formats.
+    /// For 3D textures, the region includes all the depth slices of the mipmap.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Rect, Surface};
+
+    let surface = Surface {
+        width: 256,
+        height: 256,
+        depth: 1,
+        image_data: vec![0u8; 256 * 256 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "stage")?;
+
+    // Replace a 16x8 pixel region with white pixels.
+    let rect = Rect {
+        x: 64,
+        y: 32,
+        width: 16,
+        height: 8,
+    };
+    nutexb.write_region(0, 0, rect, &[255u8; 16 * 8 * 4])?;
+    assert_eq!(vec![255u8; 16 * 8 * 4], nutexb.read_region(0, 0, rect)?);
+
+    // Only the pixels in the region are modified.
+    let data = nutexb.deswizzled_data()?;
+    assert_eq!(&[255u8; 4], &data[(32 * 256 + 64) * 4..][..4]);
+    assert_eq!(&[0u8; 4], &data[(32 * 256 + 63) * 4..][..4]);
+    # Ok(()) }
+    ```
+    */
+    pub fn read_region(&self, layer: u32, mip: u32, rect: Rect) -> Result<Vec<u8>, RegionError> {
+        region::read_region(self, layer, mip, rect)
+    }
+
+    /// Writes `data` to the blocks in `rect` for mipmap `mip` of layer `layer` without reswizzling the surface.
+    ///
+    /// The `data` uses the same layout as [NutexbFile::read_region].
+    /// Blocks outside `rect` are left unchanged.
+    pub fn write_region(
+        &mut self,
+        layer: u32,
+        mip: u32,
+        rect: Rect,
+        data: &[u8],
+    ) -> Result<(), RegionError> {
+        region::write_region(self, layer, mip, rect, data)
+    }
+
+    /// Replaces all the image data for mipmap `mip` of layer `layer` with `data` without reswizzling the rest of the surface.
+    ///
+    /// The `data` should have the untiled data for the mipmap like [Surface] with a single layer and mipmap
+    /// and the same dimensions as the mipmap.
+    /// Returns an error if the format is unknown, the layer or mipmap is out of range,
+    /// or `data` does not have exactly the number of bytes required for the mipmap.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    // A cube map with 6 layers and mipmaps of 64x64, 32x32, and 16x16 pixels.
+    let size = (64 * 64 + 32 * 32 + 16 * 16) * 4;
+    let surface = Surface {
+        width: 64,
+        height: 64,
+        depth: 1,
+        image_data: vec![0u8; size * 6],
+        mipmap_count: 3,
+        layer_count: 6,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "cube")?;
+
+    // Replace the base mipmap of the third face.
+    nutexb.replace_subresource(2, 0, &[255u8; 64 * 64 * 4])?;
+
+    let data = nutexb.deswizzled_data()?;
+    assert!(data[size * 2..][..64 * 64 * 4].iter().all(|b| *b == 255));
+    assert_eq!(64 * 64 * 4, data.iter().filter(|b| **b == 255).count());
+    # Ok(()) }
+    ```
+    */
+    pub fn replace_subresource(
+        &mut self,
+        layer: u32,
+        mip: u32,
+        data: &[u8],
+    ) -> Result<(), RegionError> {
+        region::replace_subresource(self, layer, mip, data)
+    }
+
+    /// Removes the mipmaps after the first `count` mipmaps for each layer.
+    ///
+    /// The [data](#structfield.data), [layer_mipmaps](#structfield.layer_mipmaps), and [footer](#structfield.footer)
+    /// values `mipmap_count` and `data_size` are all updated.
+    /// Returns [MipmapError::InvalidMipmapCount] if `count` is zero or higher than the current mipmap count.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 64,
+        height: 64,
+        depth: 1,
+        image_data: vec![0u8; (64 * 64 + 32 * 32 + 16 * 16 + 8 * 8) * 4],
+        mipmap_count: 4,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    nutexb.truncate_mipmaps(2)?;
+    assert_eq!(2, nutexb.footer.mipmap_count);
+    assert_eq!(vec![64 * 64 * 4, 32 * 32 * 4], nutexb.layer_mipmaps[0].mipmap_sizes);
+    assert!(nutexb.validate().is_empty());
+    # Ok(()) }
+    ```
+    */
+    pub fn truncate_mipmaps(&mut self, count: u32) -> Result<(), MipmapError> {
+        mipmaps::truncate_mipmaps(self, count)
+    }
+
+    #[cfg(feature = "image")]
+    /// Replaces all the mipmaps with `count` mipmaps generated from the base mipmap of each layer using `filter`.
+    ///
+    /// The [data](#structfield.data), [layer_mipmaps](#structfield.layer_mipmaps), and [footer](#structfield.footer)
+    /// are all updated like [NutexbFile::to_swizzled] or [NutexbFile::to_linear] depending on the current layout.
+    /// Color channels for sRGB formats are filtered in linear space.
+    ///
+    /// Only 2D textures with the uncompressed 8-bit unorm, 8-bit sRGB, [NutexbFormat::R32G32B32A32Float],
+    /// and BC formats are supported.
+    /// BC formats are decoded and encoded again after filtering, which requires the `"encoder"` feature.
+    /// Returns [MipmapError::CannotReencode] for BC formats without the `"encoder"` feature
+    /// and [MipmapError::UnsupportedFormat] for other formats.
+    /// Returns [MipmapError::InvalidMipmapCount] if `count` is zero or exceeds the number of mipmaps down to 1x1.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{image::imageops::FilterType, NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 64,
+        height: 64,
+        depth: 1,
+        image_data: vec![128u8; 64 * 64 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Srgb,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    // Generate the full mipmap chain down to 1x1.
+    nutexb.regenerate_mipmaps(7, FilterType::Triangle)?;
+    assert_eq!(7, nutexb.footer.mipmap_count);
+    assert!(nutexb.validate().is_empty());
+
+    // Filtering a solid color doesn't change the color.
+    assert_eq!(Some(&[128u8; 4][..]), nutexb.read_texel(0, 6, 0, 0, 0));
+    # Ok(()) }
+    ```
+    */
+    pub fn regenerate_mipmaps(
+        &mut self,
+        count: u32,
+        filter: image::imageops::FilterType,
+    ) -> Result<(), MipmapError> {
+        mipmaps::regenerate_mipmaps(self, count, filter)
+    }
+
+    /// Deswizzles and decodes all the layers and mipmaps in [data](#structfield.data) to RGBA8.
+    ///
+    /// Uncompressed formats are converted to RGBA8 with `0` for missing color channels and `255` for missing alpha.
+    /// Decoding BC formats is not supported.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 2,
+        height: 2,
+        depth: 1,
+        image_data: [0u8, 128, 255, 255].repeat(4),
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::B8G8R8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "bgra")?;
+
+    let rgba = nutexb.decode_rgba8()?;
+    assert_eq!([255u8, 128, 0, 255].repeat(4), rgba);
+    # Ok(()) }
+    ```
+    */
+    pub fn decode_rgba8(&self) -> Result<Vec<u8>, DecodeError> {
+        decode::decode_rgba8(self)
+    }
+
+    /// Creates a [NutexbFile] from `image` with the nutexb string set to `name`.
+    /// The data in `image` is swizzled according to the specified dimensions and format.
+    /// Names that don't fit in the footer return [SurfaceError::InvalidName].
+    ///
+    /// # Examples
+    /// Swizzled rows are padded to 64 bytes, and the block height depends on the surface height.
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let size = 33 * 17 * 8;
+    let surface = Surface {
+        width: 33,
+        height: 17,
+        depth: 1,
+        image_data: vec![0u8; size],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R16G16B16A16Float,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+    assert_eq!(10240, nutexb.data.len());
+    assert_eq!(size, nutexb.deswizzled_data()?.len());
+    # Ok(()) }
+    ```
+    */
+    pub fn from_surface<T: AsRef<[u8]>, S: Into<String>>(
+        image: Surface<T>,
+        name: S,
+    ) -> Result<Self, SurfaceError> {
+        create_nutexb(image, name)
+    }
+
+    /// Creates a [NutexbFile] like [NutexbFile::from_surface] using the specified swizzling `options`.
+    ///
+    /// The game and this library infer the block height from the dimensions,
+    /// so only use a different block height for matching files from other sources.
+    pub fn from_surface_with_options<T: AsRef<[u8]>, S: Into<String>>(
+        image: Surface<T>,
+        name: S,
+        options: SwizzleOptions,
+    ) -> Result<Self, SurfaceError> {
+        create_nutexb_with_options(image, name, Vec::new(), options)
+    }
+
+    /// Creates a [NutexbFile] like [NutexbFile::from_surface]
+    /// that reuses the allocation of `buffer` for [data](#structfield.data).
+    ///
+    /// The previous contents of `buffer` are discarded.
+    /// The allocation is only reused if `buffer` has enough capacity for the swizzled data.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let mut buffer = Vec::new();
+    for i in 0..3 {
+        let surface = Surface {
+            width: 64,
+            height: 64,
+            depth: 1,
+            image_data: vec![i; 64 * 64 * 4],
+            mipmap_count: 1,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        let mut nutexb = NutexbFile::from_surface_into(surface, "tex", buffer)?;
+        assert!(nutexb.data.iter().all(|b| *b == i));
+
+        // Take the buffer back for the next conversion.
+        buffer = std::mem::take(&mut nutexb.data);
+    }
+    # Ok(()) }
+    ```
+    */
+    pub fn from_surface_into<T: AsRef<[u8]>, S: Into<String>>(
+        image: Surface<T>,
+        name: S,
+        buffer: Vec<u8>,
+    ) -> Result<Self, SurfaceError> {
+        create_nutexb_into(image, name, buffer)
+    }
+
+    /// Creates a [NutexbFile] from `surface` with the nutexb string set to `name` without any swizzling.
+    /// Prefer [NutexbFile::from_surface] for better memory access performance in most cases.
+    ///
+    /// The image data for `surface` should contain all the mipmaps for each layer like with [NutexbFile::from_surface].
+    /// Returns [SurfaceError::DataSizeMismatch] if the image data is not exactly the size of all the layers and mipmaps.
+    ///
+    /// Textures created with [NutexbFile::from_surface] use a memory layout optimized for the Tegra X1 with better access performance in the general case.
+    /// This function exists for the rare case where swizzling the image data is not desired for performance or compatibility reasons.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    // 6 layers with mipmaps of 4x4, 2x2, and 1x1 pixels.
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; (16 + 4 + 1) * 4 * 6],
+        mipmap_count: 3,
+        layer_count: 6,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface_unswizzled(&surface, "cube")?;
+    assert_eq!(surface.image_data, nutexb.data);
+    assert_eq!(6, nutexb.layer_mipmaps.len());
+    assert_eq!(vec![64, 16, 4], nutexb.layer_mipmaps[0].mipmap_sizes);
+    # Ok(()) }
+    ```
+    */
+    pub fn from_surface_unswizzled<T: AsRef<[u8]>, S: Into<String>>(
+        surface: &Surface<T>,
+        name: S,
+    ) -> Result<Self, SurfaceError> {
+        create_nutexb_unswizzled(surface, name)
+    }
+
+    /// Creates a copy of the [NutexbFile] with all layers and mipmaps in the swizzled layout
+    /// used by [NutexbFile::from_surface].
+    ///
+    /// The [data](#structfield.data), [layer_mipmaps](#structfield.layer_mipmaps), and [footer](#structfield.footer)
+    /// values like `unk2`, `unk3`, `data_size`, and `version` are all updated.
+    /// Files that are already swizzled are returned unchanged.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 64,
+        height: 64,
+        depth: 1,
+        image_data: (0..64 * 64 * 4 * 6).map(|i| i as u8).collect::<Vec<_>>(),
+        mipmap_count: 1,
+        layer_count: 6,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let nutexb = NutexbFile::from_surface(surface.clone(), "cube")?;
+
+    let linear = nutexb.to_linear()?;
+    assert_eq!(0, linear.footer.unk3);
+    assert_eq!((2, 0), linear.footer.version);
+    assert_eq!(surface.image_data, linear.data);
+
+    let swizzled = linear.to_swizzled()?;
+    assert_eq!(0x1000, swizzled.footer.unk3);
+    assert_eq!(nutexb.data, swizzled.data);
+    # Ok(()) }
+    ```
+    */
+    pub fn to_swizzled(&self) -> Result<Self, SurfaceError> {
+        if self.footer.unk3 == 0x1000 {
+            return Ok(self.clone());
+        }
+
+        create_nutexb_with_name(
+            self.surface(&self.data),
+            self.footer.name.clone(),
+            Vec::new(),
+            SwizzleOptions::default(),
+        )
+    }
+
+    /// Creates a copy of the [NutexbFile] with all layers and mipmaps in the linear layout
+    /// used by [NutexbFile::from_surface_unswizzled].
+    ///
+    /// The [data](#structfield.data), [layer_mipmaps](#structfield.layer_mipmaps), and [footer](#structfield.footer)
+    /// values like `unk2`, `unk3`, `data_size`, and `version` are all updated.
+    /// Files that are already unswizzled are returned unchanged.
+    pub fn to_linear(&self) -> Result<Self, SurfaceError> {
+        if self.footer.unk3 != 0x1000 {
+            return Ok(self.clone());
+        }
+
+        let data = self.deswizzled_data()?;
+        Ok(create_nutexb_from_linear(
+            self.surface(data),
+            self.footer.name.clone(),
+        ))
+    }
+
+    /// A [Surface] with the dimensions and format from the [footer](#structfield.footer).
+    fn surface<T>(&self, image_data: T) -> Surface<T> {
+        Surface {
+            width: self.footer.width,
+            height: self.footer.height,
+            depth: self.footer.depth,
+            image_data,
+            mipmap_count: self.footer.mipmap_count,
+            layer_count: self.footer.layer_count,
+            image_format: self.footer.image_format,
+        }
+    }
+
+    #[cfg(feature = "ddsfile")]
+    /// Creates a swizzled [NutexbFile] from `dds` with the Nutexb string set to `name`.
+    ///
+    /// DDS supports all Nutexb image formats as well as array layers, mipmaps, cube maps, and 3D volume textures.
+    pub fn from_dds<S: Into<String>>(dds: &ddsfile::Dds, name: S) -> Result<Self, ReadDdsError> {
+        Self::from_dds_with_options(dds, name, DdsOptions::default())
+    }
+
+    #[cfg(feature = "ddsfile")]
+    /// Creates a swizzled [NutexbFile] from `dds` like [NutexbFile::from_dds] using the specified `options`.
+    ///
+    /// The DDS data must contain at least as many bytes as required by the header
+    /// or [ReadDdsError::NotEnoughData] is returned.
+    ///
+    /// Premultiplied alpha is detected from the DX10 header or legacy DXT2 and DXT4 formats.
+    /// Nutexb has no way to store the alpha mode, so set [DdsOptions::unpremultiply_alpha]
+    /// to convert premultiplied data to straight alpha.
+    /// Removing premultiplied alpha from DXT2, DXT4, and other BC formats requires the `"encoder"` feature.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{ddsfile::*, DdsOptions, NutexbFile, ReadDdsError};
+
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        height: 64,
+        width: 64,
+        depth: None,
+        format: DxgiFormat::BC7_UNorm,
+        mipmap_levels: Some(7),
+        array_layers: None,
+        caps2: None,
+        is_cubemap: false,
+        resource_dimension: D3D10ResourceDimension::Texture2D,
+        alpha_mode: AlphaMode::Unknown,
+    })?;
+
+    // Only keep the first three mipmaps.
+    dds.data.truncate((16 * 16 + 8 * 8 + 4 * 4) * 16);
+    assert!(matches!(
+        NutexbFile::from_dds(&dds, "tex"),
+        Err(ReadDdsError::NotEnoughData { expected_size: 5488, actual_size: 5376 })
+    ));
+
+    let options = DdsOptions {
+        allow_truncated_mipmaps: true,
+        ..Default::default()
+    };
+    let nutexb = NutexbFile::from_dds_with_options(&dds, "tex", options)?;
+    assert_eq!(3, nutexb.footer.mipmap_count);
+    # Ok(()) }
+    ```
+    */
+    pub fn from_dds_with_options<S: Into<String>>(
+        dds: &ddsfile::Dds,
+        name: S,
+        options: DdsOptions,
+    ) -> Result<Self, ReadDdsError> {
+        let surface = dds::create_surface(dds, options)?;
+        let image_data = if options.unpremultiply_alpha
+            && dds::alpha_mode(dds) == ddsfile::AlphaMode::PreMultiplied
+        {
+            std::borrow::Cow::Owned(dds::unpremultiply_surface(&surface)?)
+        } else {
+            std::borrow::Cow::Borrowed(surface.image_data)
+        };
+        Self::from_surface(
+            Surface {
+                width: surface.width,
+                height: surface.height,
+                depth: surface.depth,
+                image_data,
+                mipmap_count: surface.mipmap_count,
+                layer_count: surface.layer_count,
+                image_format: surface.image_format,
+            },
+            name,
+        )
+        .map_err(Into::into)
+    }
+
+    /// Deswizzle the surface data to DDS while preserving the layers, mipmaps, and image format.
+    ///
+    /// The DDS alpha mode is [ddsfile::AlphaMode::Opaque] if the format has no alpha channel
+    /// or every pixel is fully opaque and [ddsfile::AlphaMode::Straight] otherwise.
+    /// BC7 data with blocks that can store alpha uses [ddsfile::AlphaMode::Unknown],
+    /// since detecting transparency would require decoding the blocks.
+    /// Use [NutexbFile::to_dds_with_alpha_mode] for data with premultiplied alpha.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{ddsfile::AlphaMode, NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![255u8; 4 * 4 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Srgb,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "opaque")?;
+
+    let dds = nutexb.to_dds()?;
+    assert_eq!(AlphaMode::Opaque, dds.header10.unwrap().alpha_mode);
+    # Ok(()) }
+    ```
+    */
+    #[cfg(feature = "ddsfile")]
+    pub fn to_dds(&self) -> Result<ddsfile::Dds, SurfaceError> {
+        dds::create_dds(self, None)
+    }
 
-impl NutexbFile {
-    /// Reads the [NutexbFile] from the specified `reader`.
-    pub fn read<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
-        reader.read_le::<NutexbFile>()
+    /// Deswizzle the surface data to DDS like [NutexbFile::to_dds] but with the specified `alpha_mode`.
+    #[cfg(feature = "ddsfile")]
+    pub fn to_dds_with_alpha_mode(
+        &self,
+        alpha_mode: ddsfile::AlphaMode,
+    ) -> Result<ddsfile::Dds, SurfaceError> {
+        dds::create_dds(self, Some(alpha_mode))
     }
 
-    /// Reads the [NutexbFile] from the specified `path`.
-    /// The entire file is buffered to improve performance.
-    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<NutexbFile, binrw::Error> {
-        let mut file = Cursor::new(std::fs::read(path)?);
-        let nutexb = file.read_le::<NutexbFile>()?;
-        Ok(nutexb)
+    #[cfg(feature = "ktx2")]
+    /// Creates a swizzled [NutexbFile] from `ktx2` with the Nutexb string set to `name`.
+    ///
+    /// KTX2 supports all Nutexb image formats as well as array layers, mipmaps, cube maps, and 3D volume textures.
+    /// Supercompressed KTX2 files are not supported.
+    pub fn from_ktx2<D: AsRef<[u8]>, S: Into<String>>(
+        ktx2: &ktx2::Reader<D>,
+        name: S,
+    ) -> Result<Self, ReadKtx2Error> {
+        let surface = ktx::create_surface(ktx2)?;
+        Self::from_surface(surface, name).map_err(Into::into)
     }
 
-    /// Writes the [NutexbFile] to the specified `writer`.
-    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
-        self.write_le(writer).map_err(Into::into)
-    }
+    /// Deswizzle the surface data to the bytes of a KTX2 file while preserving the layers, mipmaps, and image format.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
 
-    /// Writes the [NutexbFile] to the specified `path`.
-    /// The entire file is buffered to improve performance.
-    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), binrw::Error> {
-        let mut writer = Cursor::new(Vec::new());
-        self.write(&mut writer)?;
-        std::fs::write(path, writer.into_inner()).map_err(Into::into)
-    }
+    let surface = Surface {
+        width: 16,
+        height: 16,
+        depth: 1,
+        image_data: (0..16 * 16 * 4 * 6).map(|i| i as u8).collect::<Vec<_>>(),
+        mipmap_count: 1,
+        layer_count: 6,
+        image_format: NutexbFormat::R8G8B8A8Srgb,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "cube")?;
 
-    /// Deswizzles all the layers and mipmaps in [data](#structfield.data).
-    pub fn deswizzled_data(&self) -> Result<Vec<u8>, tegra_swizzle::SwizzleError> {
-        tegra_swizzle::surface::deswizzle_surface(
-            self.footer.width,
-            self.footer.height,
-            self.footer.depth,
-            &self.data,
-            self.footer.image_format.block_dim(),
-            None,
-            self.footer.image_format.bytes_per_pixel(),
-            self.footer.mipmap_count,
-            self.footer.layer_count,
-        )
-    }
+    let bytes = nutexb.to_ktx2()?;
+    let ktx2 = nutexb::ktx2::Reader::new(&bytes)?;
+    assert_eq!(6, ktx2.header().face_count);
 
-    /// Creates a [NutexbFile] from `image` with the nutexb string set to `name`.
-    /// The data in `image` is swizzled according to the specified dimensions and format.
-    pub fn from_surface<T: AsRef<[u8]>, S: Into<String>>(
-        image: Surface<T>,
-        name: S,
-    ) -> Result<Self, tegra_swizzle::SwizzleError> {
-        create_nutexb(image, name)
+    let new_nutexb = NutexbFile::from_ktx2(&ktx2, "cube")?;
+    assert_eq!(nutexb.data, new_nutexb.data);
+    # Ok(()) }
+    ```
+    */
+    #[cfg(feature = "ktx2")]
+    pub fn to_ktx2(&self) -> Result<Vec<u8>, SurfaceError> {
+        ktx::create_ktx2(self)
     }
 
-    /// Creates a [NutexbFile] from `surface` with the nutexb string set to `name` without any swizzling.
-    /// This assumes no layers or mipmaps for `image`.
-    /// Prefer [NutexbFile::from_surface] for better memory access performance in most cases.
+    /// Creates a swizzled [NutexbFile] from `texture` with the Nutexb string set to the texture name.
     ///
-    /// Textures created with [NutexbFile::from_surface] use a memory layout optimized for the Tegra X1 with better access performance in the general case.
-    /// This function exists for the rare case where swizzling the image data is not desired for performance or compatibility reasons.
-    pub fn from_surface_unswizzled<T: AsRef<[u8]>, S: Into<String>>(
-        surface: &Surface<T>,
-        name: S,
-    ) -> Self {
-        create_nutexb_unswizzled(surface, name)
+    /// BNTX and nutexb use the same swizzled memory layout,
+    /// so the image data is only swizzled again if `texture` uses a different block height.
+    pub fn from_bntx_texture(texture: &BntxTexture) -> Result<Self, ReadBntxError> {
+        bntx::create_nutexb(texture)
     }
 
-    #[cfg(feature = "ddsfile")]
-    /// Creates a swizzled [NutexbFile] from `dds` with the Nutexb string set to `name`.
+    /// Creates a swizzled [BntxTexture] while preserving the layers, mipmaps, and image format.
     ///
-    /// DDS supports all Nutexb image formats as well as array layers, mipmaps, cube maps, and 3D volume textures.
-    pub fn from_dds<S: Into<String>>(dds: &ddsfile::Dds, name: S) -> Result<Self, ReadDdsError> {
-        let surface = dds::create_surface(dds)?;
-        Self::from_surface(surface, name).map_err(Into::into)
-    }
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{BntxFile, NutexbFile, NutexbFormat, Surface};
 
-    /// Deswizzle the surface data to DDS while preserving the layers, mipmaps, and image format.
-    #[cfg(feature = "ddsfile")]
-    pub fn to_dds(&self) -> Result<ddsfile::Dds, tegra_swizzle::SwizzleError> {
-        dds::create_dds(&self)
+    let surface = Surface {
+        width: 64,
+        height: 64,
+        depth: 1,
+        image_data: (0..5488).map(|i| i as u8).collect::<Vec<_>>(),
+        mipmap_count: 7,
+        layer_count: 1,
+        image_format: NutexbFormat::BC7Srgb,
+    };
+    let nutexb = NutexbFile::from_surface(surface, "tex")?;
+
+    let bntx = BntxFile {
+        name: "textures".to_string(),
+        textures: vec![nutexb.to_bntx_texture()?],
+    };
+    let mut writer = std::io::Cursor::new(Vec::new());
+    bntx.write(&mut writer)?;
+
+    writer.set_position(0);
+    let new_bntx = BntxFile::read(&mut writer)?;
+    assert_eq!(bntx, new_bntx);
+
+    let new_nutexb = NutexbFile::from_bntx_texture(&new_bntx.textures[0])?;
+    assert_eq!(nutexb.data, new_nutexb.data);
+    assert_eq!(nutexb.footer, new_nutexb.footer);
+    # Ok(()) }
+    ```
+    */
+    pub fn to_bntx_texture(&self) -> Result<BntxTexture, SurfaceError> {
+        bntx::create_bntx_texture(self)
     }
 
     #[cfg(feature = "image")]
@@ -217,7 +1720,7 @@ impl NutexbFile {
     pub fn from_image<S: Into<String>>(
         image: &image::RgbaImage,
         name: S,
-    ) -> Result<Self, tegra_swizzle::SwizzleError> {
+    ) -> Result<Self, SurfaceError> {
         let surface = Surface {
             width: image.width(),
             height: image.height(),
@@ -234,21 +1737,130 @@ impl NutexbFile {
     ///
     /// Calling this method is unnecessary for nutexbs created with [NutexbFile::from_surface] or [NutexbFile::from_surface_unswizzled].
     /// These methods already calculate the appropriate image data size.
+    /// The data for [NutexbFormat::Unknown] is not modified since the expected size isn't known.
+    /// The data is also not modified if the dimensions are too large for the size to be calculated.
     pub fn optimize_size(&mut self) {
-        let new_len = if self.footer.unk3 == 0x1000 {
-            swizzled_surface_size(
+        self.optimize_size_with_options(SwizzleOptions::default())
+    }
+
+    /// Resizes the image data like [NutexbFile::optimize_size] using the specified swizzling `options`
+    /// to calculate the expected size.
+    pub fn optimize_size_with_options(&mut self, options: SwizzleOptions) {
+        if let Some(new_len) = self.expected_data_size_with_options(options) {
+            // Remove padding and align the surface to the appropriate size.
+            self.data.resize(new_len, 0);
+            self.footer.data_size = self.data.len() as u32;
+        }
+    }
+
+    /// Checks the [footer](#structfield.footer), [layer_mipmaps](#structfield.layer_mipmaps),
+    /// and [data](#structfield.data) for inconsistencies.
+    /// Nutexbs created with [NutexbFile::from_surface] have no issues.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Severity, Surface, ValidationIssue};
+
+    let surface = Surface {
+        width: 8,
+        height: 8,
+        depth: 1,
+        image_data: vec![0u8; 8 * 8 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+    assert!(nutexb.validate().is_empty());
+
+    nutexb.footer.data_size = 0;
+    let issues = nutexb.validate();
+    assert_eq!(
+        vec![ValidationIssue::FooterDataSizeMismatch {
+            data_size: 0,
+            data_length: nutexb.data.len()
+        }],
+        issues
+    );
+    assert_eq!(Severity::Error, issues[0].severity());
+    # Ok(()) }
+    ```
+    */
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validate::validate(self)
+    }
+
+    /// Fixes the repairable issues found by [NutexbFile::validate] and returns the remaining issues.
+    ///
+    /// Mipmap counts are clamped to the dimensions,
+    /// and the mipmap sizes and data size are recalculated from the footer.
+    /// Image data is truncated or padded with zeros like [NutexbFile::optimize_size].
+    /// Names without room for a null terminator are truncated.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+    let surface = Surface {
+        width: 8,
+        height: 8,
+        depth: 1,
+        image_data: vec![0u8; 8 * 8 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+    nutexb.footer.mipmap_count = 20;
+    nutexb.data.truncate(16);
+    assert_eq!(3, nutexb.validate().len());
+
+    assert!(nutexb.repair().is_empty());
+    assert_eq!(4, nutexb.footer.mipmap_count);
+    # Ok(()) }
+    ```
+    */
+    pub fn repair(&mut self) -> Vec<ValidationIssue> {
+        validate::repair(self)
+    }
+
+    /// The size of [data](#structfield.data) based on the [footer](#structfield.footer)
+    /// or `None` if the size can't be calculated.
+    pub(crate) fn expected_data_size(&self) -> Option<usize> {
+        self.expected_data_size_with_options(SwizzleOptions::default())
+    }
+
+    fn expected_data_size_with_options(&self, options: SwizzleOptions) -> Option<usize> {
+        if check_format(self.footer.image_format).is_err()
+            || !is_valid_surface(
+                self.footer.width,
+                self.footer.height,
+                self.footer.depth,
+                self.footer.image_format.bytes_per_pixel(),
+                self.footer.mipmap_count,
+            )
+        {
+            return None;
+        }
+
+        if self.footer.unk3 == 0x1000 {
+            Some(swizzled_surface_size(
                 self.footer.width,
                 self.footer.height,
                 self.footer.depth,
                 self.footer.image_format.block_dim(),
-                None,
+                options.block_height,
                 self.footer.image_format.bytes_per_pixel(),
                 self.footer.mipmap_count,
                 self.footer.layer_count,
-            )
+            ))
         } else {
             // Not all nutexbs store swizzled surfaces.
-            deswizzled_surface_size(
+            Some(deswizzled_surface_size(
                 self.footer.width,
                 self.footer.height,
                 self.footer.depth,
@@ -256,12 +1868,8 @@ impl NutexbFile {
                 self.footer.image_format.bytes_per_pixel(),
                 self.footer.mipmap_count,
                 self.footer.layer_count,
-            )
-        };
-
-        // Remove padding and align the surface to the appropriate size.
-        self.data.resize(new_len, 0);
-        self.footer.data_size = self.data.len() as u32;
+            ))
+        }
     }
 }
 
@@ -270,11 +1878,14 @@ impl NutexbFile {
 #[derive(Debug, Clone, PartialEq)]
 #[brw(magic = b" XNT")]
 pub struct NutexbFooter {
-    // TODO: Make this field "name: String"
-    // TODO: Names can be at most 63 characters + 1 null byte?
-    /// The name of the texture, which usually matches the file name without its extension like `"def_001_col"`.
-    #[brw(pad_size_to = 0x40)]
-    pub string: NullString,
+    // Access the name through methods to keep it within the padded field.
+    #[bw(pad_size_to = 0x40)]
+    name: TextureName,
+    /// The bytes in the name field after the null terminator.
+    /// These are only written with [WriteMode::RoundTrip] and are usually zero.
+    #[br(count = 0x40usize.saturating_sub(name.as_bytes().len() + 1))]
+    #[bw(ignore)]
+    pub name_padding: Vec<u8>,
     /// The width of the texture in pixels.
     pub width: u32,
     /// The height of the texture in pixels.
@@ -296,15 +1907,84 @@ pub struct NutexbFooter {
     pub version: (u16, u16),
 }
 
+impl NutexbFooter {
+    /// The name of the texture, which usually matches the file name without its extension like `"def_001_col"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the name of the texture or returns an error if `name` does not fit in the footer.
+    /// Use [TextureName::truncated] to shorten long names instead.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface, TextureName};
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![0u8; 64],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+    assert_eq!("tex", nutexb.footer.name());
+
+    let long_name = "def_".repeat(20);
+    assert!(nutexb.footer.set_name(long_name.clone()).is_err());
+
+    nutexb.footer.set_name(TextureName::truncated(long_name))?;
+    assert_eq!(63, nutexb.footer.name().len());
+    # Ok(()) }
+    ```
+    */
+    pub fn set_name<S: Into<String>>(&mut self, name: S) -> Result<(), TextureNameError> {
+        self.name = TextureName::new(name)?;
+        self.name_padding.clear();
+        Ok(())
+    }
+
+    /// The layout of the swizzled surface described by the footer using the specified `options`.
+    pub(crate) fn layout_with_options(&self, options: SwizzleOptions) -> SurfaceLayout {
+        SurfaceLayout {
+            block_height_mip0: options.block_height,
+            ..self.layout()
+        }
+    }
+
+    /// The layout of the swizzled surface described by the footer.
+    pub(crate) fn layout(&self) -> SurfaceLayout {
+        SurfaceLayout {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            block_dim: self.image_format.block_dim(),
+            block_height_mip0: None,
+            bytes_per_pixel: self.image_format.bytes_per_pixel(),
+            mipmap_count: self.mipmap_count,
+            layer_count: self.layer_count,
+        }
+    }
+}
+
 /// The mipmap sizes for each array layer.
 #[binrw]
 #[derive(Debug, Clone)]
 #[br(import(mipmap_count: u32))]
 pub struct LayerMipmaps {
     /// The size in bytes of the deswizzled data for each mipmap.
-    #[brw(pad_size_to = 0x40)]
+    #[bw(pad_size_to = 0x40)]
     #[br(count = mipmap_count)]
     pub mipmap_sizes: Vec<u32>,
+    /// The bytes after the mipmap sizes.
+    /// These are only written with [WriteMode::RoundTrip] and are usually zero.
+    #[br(count = LAYER_MIPMAPS_SIZE.saturating_sub(mipmap_sizes.len() * 4))]
+    #[bw(ignore)]
+    pub padding: Vec<u8>,
 }
 
 /// Supported image data formats.
@@ -317,43 +1997,174 @@ pub struct LayerMipmaps {
 /// "Srgb", "Unorm", and "Snorm" variants use the same data format but use different conversions to floating point when accessed by a GPU shader.
 // TODO: It's possible this is some sort of flags.
 // ex: num channels, format, type (srgb, unorm, etc)?
-#[derive(Debug, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
-#[brw(repr(u32))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NutexbFormat {
-    R8Unorm = 0x0100,
-    R8G8B8A8Unorm = 0x0400,
-    R8G8B8A8Srgb = 0x0405,
-    R32G32B32A32Float = 0x0434,
-    B8G8R8A8Unorm = 0x0450,
-    B8G8R8A8Srgb = 0x0455,
-    BC1Unorm = 0x0480,
-    BC1Srgb = 0x0485,
-    BC2Unorm = 0x0490,
-    BC2Srgb = 0x0495,
-    BC3Unorm = 0x04a0,
-    BC3Srgb = 0x04a5,
-    BC4Unorm = 0x0180,
-    BC4Snorm = 0x0185,
-    BC5Unorm = 0x0280,
-    BC5Snorm = 0x0285,
-    BC6Ufloat = 0x04d7,
-    BC6Sfloat = 0x04d8,
-    BC7Unorm = 0x04e0,
-    BC7Srgb = 0x04e5,
+    R8Unorm,
+    R8G8B8A8Unorm,
+    R8G8B8A8Srgb,
+    R32G32B32A32Float,
+    B8G8R8A8Unorm,
+    B8G8R8A8Srgb,
+    R16G16B16A16Float,
+    BC1Unorm,
+    BC1Srgb,
+    BC2Unorm,
+    BC2Srgb,
+    BC3Unorm,
+    BC3Srgb,
+    BC4Unorm,
+    BC4Snorm,
+    BC5Unorm,
+    BC5Snorm,
+    BC6Ufloat,
+    BC6Sfloat,
+    BC7Unorm,
+    BC7Srgb,
+    /// A format value that doesn't match any known format.
+    ///
+    /// Files with unknown formats can still be read and written,
+    /// but conversions and swizzling return an error since the data layout isn't known.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use nutexb::{NutexbFile, NutexbFormat, Surface, SurfaceError};
+    use std::io::Cursor;
+
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![255u8; 4 * 4 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    let mut nutexb = NutexbFile::from_surface(surface, "tex")?;
+    nutexb.footer.image_format = NutexbFormat::Unknown(0x1234);
+
+    let mut writer = Cursor::new(Vec::new());
+    nutexb.write(&mut writer)?;
+    let bytes = writer.into_inner();
+
+    let new_nutexb = NutexbFile::read(&mut Cursor::new(&bytes))?;
+    assert_eq!(NutexbFormat::Unknown(0x1234), new_nutexb.footer.image_format);
+    assert_eq!(4, new_nutexb.footer.width);
+
+    let mut writer = Cursor::new(Vec::new());
+    new_nutexb.write(&mut writer)?;
+    assert_eq!(bytes, writer.into_inner());
+
+    assert!(matches!(
+        new_nutexb.deswizzled_data(),
+        Err(SurfaceError::UnsupportedFormat(NutexbFormat::Unknown(0x1234)))
+    ));
+    # Ok(()) }
+    ```
+    */
+    Unknown(u32),
+}
+
+impl From<NutexbFormat> for u32 {
+    fn from(value: NutexbFormat) -> Self {
+        match value {
+            NutexbFormat::R8Unorm => 0x0100,
+            NutexbFormat::R8G8B8A8Unorm => 0x0400,
+            NutexbFormat::R8G8B8A8Srgb => 0x0405,
+            NutexbFormat::R32G32B32A32Float => 0x0434,
+            NutexbFormat::B8G8R8A8Unorm => 0x0450,
+            NutexbFormat::B8G8R8A8Srgb => 0x0455,
+            NutexbFormat::R16G16B16A16Float => 0x0424,
+            NutexbFormat::BC1Unorm => 0x0480,
+            NutexbFormat::BC1Srgb => 0x0485,
+            NutexbFormat::BC2Unorm => 0x0490,
+            NutexbFormat::BC2Srgb => 0x0495,
+            NutexbFormat::BC3Unorm => 0x04a0,
+            NutexbFormat::BC3Srgb => 0x04a5,
+            NutexbFormat::BC4Unorm => 0x0180,
+            NutexbFormat::BC4Snorm => 0x0185,
+            NutexbFormat::BC5Unorm => 0x0280,
+            NutexbFormat::BC5Snorm => 0x0285,
+            NutexbFormat::BC6Ufloat => 0x04d7,
+            NutexbFormat::BC6Sfloat => 0x04d8,
+            NutexbFormat::BC7Unorm => 0x04e0,
+            NutexbFormat::BC7Srgb => 0x04e5,
+            NutexbFormat::Unknown(value) => value,
+        }
+    }
+}
+
+impl From<u32> for NutexbFormat {
+    fn from(value: u32) -> Self {
+        match value {
+            0x0100 => NutexbFormat::R8Unorm,
+            0x0400 => NutexbFormat::R8G8B8A8Unorm,
+            0x0405 => NutexbFormat::R8G8B8A8Srgb,
+            0x0434 => NutexbFormat::R32G32B32A32Float,
+            0x0450 => NutexbFormat::B8G8R8A8Unorm,
+            0x0455 => NutexbFormat::B8G8R8A8Srgb,
+            0x0424 => NutexbFormat::R16G16B16A16Float,
+            0x0480 => NutexbFormat::BC1Unorm,
+            0x0485 => NutexbFormat::BC1Srgb,
+            0x0490 => NutexbFormat::BC2Unorm,
+            0x0495 => NutexbFormat::BC2Srgb,
+            0x04a0 => NutexbFormat::BC3Unorm,
+            0x04a5 => NutexbFormat::BC3Srgb,
+            0x0180 => NutexbFormat::BC4Unorm,
+            0x0185 => NutexbFormat::BC4Snorm,
+            0x0280 => NutexbFormat::BC5Unorm,
+            0x0285 => NutexbFormat::BC5Snorm,
+            0x04d7 => NutexbFormat::BC6Ufloat,
+            0x04d8 => NutexbFormat::BC6Sfloat,
+            0x04e0 => NutexbFormat::BC7Unorm,
+            0x04e5 => NutexbFormat::BC7Srgb,
+            _ => NutexbFormat::Unknown(value),
+        }
+    }
+}
+
+// Unknown values are preserved to allow inspecting and rewriting files with unsupported formats.
+impl BinRead for NutexbFormat {
+    type Args<'arg> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        u32::read_options(reader, endian, ()).map(Into::into)
+    }
+}
+
+impl BinWrite for NutexbFormat {
+    type Args<'arg> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        u32::from(*self).write_options(writer, endian, ())
+    }
 }
 
 impl NutexbFormat {
     /// The number of bytes per pixel.
     /// For block compressed formats like [NutexbFormat::BC7Srgb], this is the size in bytes of a single block.
+    /// This is `0` for [NutexbFormat::Unknown] since the size isn't known.
     /// # Examples
     /**
     ```rust
     # use nutexb::NutexbFormat;
     assert_eq!(1, NutexbFormat::R8Unorm.bytes_per_pixel());
     assert_eq!(4, NutexbFormat::R8G8B8A8Unorm.bytes_per_pixel());
+    assert_eq!(8, NutexbFormat::R16G16B16A16Float.bytes_per_pixel());
     assert_eq!(8, NutexbFormat::BC1Unorm.bytes_per_pixel());
     assert_eq!(16, NutexbFormat::BC7Srgb.bytes_per_pixel());
     assert_eq!(16, NutexbFormat::R32G32B32A32Float.bytes_per_pixel());
+    assert_eq!(0, NutexbFormat::Unknown(0x1234).bytes_per_pixel());
     ```
     */
     pub fn bytes_per_pixel(&self) -> u32 {
@@ -363,6 +2174,7 @@ impl NutexbFormat {
             | NutexbFormat::B8G8R8A8Unorm
             | NutexbFormat::B8G8R8A8Srgb => 4,
             NutexbFormat::R32G32B32A32Float => 16,
+            NutexbFormat::R16G16B16A16Float => 8,
             NutexbFormat::BC1Unorm | NutexbFormat::BC1Srgb => 8,
             NutexbFormat::BC2Unorm | NutexbFormat::BC2Srgb => 16,
             NutexbFormat::BC3Unorm | NutexbFormat::BC3Srgb => 16,
@@ -371,10 +2183,11 @@ impl NutexbFormat {
             NutexbFormat::BC6Ufloat | NutexbFormat::BC6Sfloat => 16,
             NutexbFormat::BC7Unorm | NutexbFormat::BC7Srgb => 16,
             NutexbFormat::R8Unorm => 1,
+            NutexbFormat::Unknown(_) => 0,
         }
     }
 
-    /// The width in pixels for a compressed block or `1` for uncompressed formats.
+    /// The width in pixels for a compressed block or `1` for uncompressed and unknown formats.
     ///
     /// # Examples
     /**
@@ -393,12 +2206,14 @@ impl NutexbFormat {
             | NutexbFormat::R8G8B8A8Srgb
             | NutexbFormat::R32G32B32A32Float
             | NutexbFormat::B8G8R8A8Unorm
-            | NutexbFormat::B8G8R8A8Srgb => 1,
+            | NutexbFormat::B8G8R8A8Srgb
+            | NutexbFormat::R16G16B16A16Float
+            | NutexbFormat::Unknown(_) => 1,
             _ => 4,
         }
     }
 
-    /// The height in pixels for a compressed block or `1` for uncompressed formats.
+    /// The height in pixels for a compressed block or `1` for uncompressed and unknown formats.
     ///
     /// # Examples
     /**
@@ -432,6 +2247,171 @@ impl NutexbFormat {
         1
     }
 
+    /// Iterates over all the known formats in declaration order.
+    /// This does not include [NutexbFormat::Unknown].
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    let srgb_count = NutexbFormat::iter().filter(|f| f.is_srgb()).count();
+    assert_eq!(6, srgb_count);
+    ```
+    */
+    pub fn iter() -> impl Iterator<Item = NutexbFormat> {
+        KNOWN_FORMATS.into_iter()
+    }
+
+    /// Returns `true` if the format uses block compression like BC7.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert!(NutexbFormat::BC7Srgb.is_compressed());
+    assert!(!NutexbFormat::R8G8B8A8Unorm.is_compressed());
+    assert!(!NutexbFormat::Unknown(0x1234).is_compressed());
+    ```
+    */
+    pub fn is_compressed(&self) -> bool {
+        // Uncompressed formats are treated as 1x1 blocks.
+        self.block_width() > 1 || self.block_height() > 1
+    }
+
+    /// Returns `true` if the color channels use the sRGB transfer function.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert!(NutexbFormat::BC7Srgb.is_srgb());
+    assert!(!NutexbFormat::BC7Unorm.is_srgb());
+    ```
+    */
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            NutexbFormat::R8G8B8A8Srgb
+                | NutexbFormat::B8G8R8A8Srgb
+                | NutexbFormat::BC1Srgb
+                | NutexbFormat::BC2Srgb
+                | NutexbFormat::BC3Srgb
+                | NutexbFormat::BC7Srgb
+        )
+    }
+
+    /// Returns `true` if the format can store negative values.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert!(NutexbFormat::BC5Snorm.is_signed());
+    assert!(NutexbFormat::R16G16B16A16Float.is_signed());
+    assert!(!NutexbFormat::BC6Ufloat.is_signed());
+    ```
+    */
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            NutexbFormat::R32G32B32A32Float
+                | NutexbFormat::R16G16B16A16Float
+                | NutexbFormat::BC4Snorm
+                | NutexbFormat::BC5Snorm
+                | NutexbFormat::BC6Sfloat
+        )
+    }
+
+    /// Returns `true` if the format stores floating point values instead of normalized integers.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert!(NutexbFormat::R16G16B16A16Float.is_float());
+    assert!(NutexbFormat::BC6Ufloat.is_float());
+    assert!(!NutexbFormat::BC7Unorm.is_float());
+    ```
+    */
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            NutexbFormat::R32G32B32A32Float
+                | NutexbFormat::R16G16B16A16Float
+                | NutexbFormat::BC6Ufloat
+                | NutexbFormat::BC6Sfloat
+        )
+    }
+
+    /// The number of color and alpha channels stored by the format.
+    /// This is `0` for [NutexbFormat::Unknown] since the channels aren't known.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert_eq!(1, NutexbFormat::BC4Unorm.channel_count());
+    assert_eq!(2, NutexbFormat::BC5Unorm.channel_count());
+    assert_eq!(3, NutexbFormat::BC6Ufloat.channel_count());
+    assert_eq!(4, NutexbFormat::BC7Srgb.channel_count());
+    ```
+    */
+    pub fn channel_count(&self) -> u32 {
+        match self {
+            NutexbFormat::R8Unorm | NutexbFormat::BC4Unorm | NutexbFormat::BC4Snorm => 1,
+            NutexbFormat::BC5Unorm | NutexbFormat::BC5Snorm => 2,
+            NutexbFormat::BC6Ufloat | NutexbFormat::BC6Sfloat => 3,
+            NutexbFormat::Unknown(_) => 0,
+            // BC1 can encode alpha even if most textures are opaque.
+            _ => 4,
+        }
+    }
+
+    /// The sRGB variant of the format or the original format if there is no sRGB variant.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert_eq!(NutexbFormat::BC7Srgb, NutexbFormat::BC7Unorm.to_srgb());
+    assert_eq!(NutexbFormat::BC7Srgb, NutexbFormat::BC7Srgb.to_srgb());
+    assert_eq!(NutexbFormat::BC4Unorm, NutexbFormat::BC4Unorm.to_srgb());
+    ```
+    */
+    pub fn to_srgb(&self) -> NutexbFormat {
+        match self {
+            NutexbFormat::R8G8B8A8Unorm => NutexbFormat::R8G8B8A8Srgb,
+            NutexbFormat::B8G8R8A8Unorm => NutexbFormat::B8G8R8A8Srgb,
+            NutexbFormat::BC1Unorm => NutexbFormat::BC1Srgb,
+            NutexbFormat::BC2Unorm => NutexbFormat::BC2Srgb,
+            NutexbFormat::BC3Unorm => NutexbFormat::BC3Srgb,
+            NutexbFormat::BC7Unorm => NutexbFormat::BC7Srgb,
+            _ => *self,
+        }
+    }
+
+    /// The linear variant of the format or the original format if there is no sRGB variant.
+    ///
+    /// # Examples
+    /**
+    ```rust
+    # use nutexb::NutexbFormat;
+    assert_eq!(NutexbFormat::BC1Unorm, NutexbFormat::BC1Srgb.to_linear());
+    assert_eq!(NutexbFormat::BC1Unorm, NutexbFormat::BC1Unorm.to_linear());
+    ```
+    */
+    pub fn to_linear(&self) -> NutexbFormat {
+        match self {
+            NutexbFormat::R8G8B8A8Srgb => NutexbFormat::R8G8B8A8Unorm,
+            NutexbFormat::B8G8R8A8Srgb => NutexbFormat::B8G8R8A8Unorm,
+            NutexbFormat::BC1Srgb => NutexbFormat::BC1Unorm,
+            NutexbFormat::BC2Srgb => NutexbFormat::BC2Unorm,
+            NutexbFormat::BC3Srgb => NutexbFormat::BC3Unorm,
+            NutexbFormat::BC7Srgb => NutexbFormat::BC7Unorm,
+            _ => *self,
+        }
+    }
+
     pub(crate) fn block_dim(&self) -> BlockDim {
         BlockDim {
             width: NonZeroU32::new(self.block_width()).unwrap(),
@@ -440,3 +2420,84 @@ impl NutexbFormat {
         }
     }
 }
+
+const KNOWN_FORMATS: [NutexbFormat; 21] = [
+    NutexbFormat::R8Unorm,
+    NutexbFormat::R8G8B8A8Unorm,
+    NutexbFormat::R8G8B8A8Srgb,
+    NutexbFormat::R32G32B32A32Float,
+    NutexbFormat::B8G8R8A8Unorm,
+    NutexbFormat::B8G8R8A8Srgb,
+    NutexbFormat::R16G16B16A16Float,
+    NutexbFormat::BC1Unorm,
+    NutexbFormat::BC1Srgb,
+    NutexbFormat::BC2Unorm,
+    NutexbFormat::BC2Srgb,
+    NutexbFormat::BC3Unorm,
+    NutexbFormat::BC3Srgb,
+    NutexbFormat::BC4Unorm,
+    NutexbFormat::BC4Snorm,
+    NutexbFormat::BC5Unorm,
+    NutexbFormat::BC5Snorm,
+    NutexbFormat::BC6Ufloat,
+    NutexbFormat::BC6Sfloat,
+    NutexbFormat::BC7Unorm,
+    NutexbFormat::BC7Srgb,
+];
+
+/// Displays the variant name like `"BC7Srgb"` or `"Unknown(0x1234)"`.
+impl Display for NutexbFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NutexbFormat::Unknown(value) => write!(f, "Unknown(0x{value:X})"),
+            _ => write!(f, "{self:?}"),
+        }
+    }
+}
+
+/// An error parsing a [NutexbFormat] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNutexbFormatError(String);
+
+impl Display for ParseNutexbFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unrecognized nutexb format name {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseNutexbFormatError {}
+
+/// Parses the variant names used by [Display] ignoring ASCII case.
+///
+/// # Examples
+/**
+```rust
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use nutexb::NutexbFormat;
+
+assert_eq!(NutexbFormat::BC7Srgb, "BC7Srgb".parse()?);
+assert_eq!(NutexbFormat::R8G8B8A8Unorm, "r8g8b8a8unorm".parse()?);
+assert_eq!(NutexbFormat::Unknown(0x1234), "Unknown(0x1234)".parse()?);
+assert!("BC8Unorm".parse::<NutexbFormat>().is_err());
+
+for format in NutexbFormat::iter() {
+    assert_eq!(format, format.to_string().parse()?);
+}
+# Ok(()) }
+```
+*/
+impl FromStr for NutexbFormat {
+    type Err = ParseNutexbFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = s
+            .strip_prefix("Unknown(0x")
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+
+        Self::iter()
+            .find(|f| f.to_string().eq_ignore_ascii_case(s))
+            .or_else(|| unknown.map(NutexbFormat::from))
+            .ok_or_else(|| ParseNutexbFormatError(s.to_string()))
+    }
+}
@@ -0,0 +1,537 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    convert::{calculate_layer_mip_sizes, check_format},
+    swizzle::SurfaceLayout,
+    NutexbFile, NutexbFormat,
+};
+
+/// Errors while changing the mipmaps of a [NutexbFile].
+#[derive(Debug)]
+pub enum MipmapError {
+    /// The image format is unknown or mipmaps can't be generated for the format.
+    UnsupportedFormat(NutexbFormat),
+    /// The format is block compressed, and encoding the new mipmaps requires the `"encoder"` feature.
+    CannotReencode(NutexbFormat),
+    /// Mipmaps can't be generated for 3D textures.
+    UnsupportedDepth(u32),
+    /// The mipmap count is zero or higher than the maximum for the texture.
+    InvalidMipmapCount {
+        mipmap_count: u32,
+        max_mipmap_count: u32,
+    },
+    /// The image data could not be swizzled or deswizzled.
+    SwizzleError(tegra_swizzle::SwizzleError),
+    /// The block compressed image data could not be decoded or encoded.
+    #[cfg(feature = "encoder")]
+    CompressionError(image_dds::error::SurfaceError),
+}
+
+impl Display for MipmapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MipmapError::UnsupportedFormat(format) => {
+                write!(f, "changing mipmaps for {format:?} is not supported")
+            }
+            MipmapError::CannotReencode(format) => write!(
+                f,
+                "encoding mipmaps for {format:?} requires the \"encoder\" feature"
+            ),
+            MipmapError::UnsupportedDepth(depth) => write!(
+                f,
+                "generating mipmaps for 3D textures with depth {depth} is not supported"
+            ),
+            MipmapError::InvalidMipmapCount {
+                mipmap_count,
+                max_mipmap_count,
+            } => write!(
+                f,
+                "mipmap count {mipmap_count} is not in the range 1 to {max_mipmap_count}"
+            ),
+            MipmapError::SwizzleError(e) => write!(f, "failed to swizzle surface: {e}"),
+            #[cfg(feature = "encoder")]
+            MipmapError::CompressionError(e) => {
+                write!(f, "failed to decode or encode surface: {e}")
+            }
+        }
+    }
+}
+
+impl From<tegra_swizzle::SwizzleError> for MipmapError {
+    fn from(value: tegra_swizzle::SwizzleError) -> Self {
+        Self::SwizzleError(value)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl From<image_dds::error::SurfaceError> for MipmapError {
+    fn from(value: image_dds::error::SurfaceError) -> Self {
+        Self::CompressionError(value)
+    }
+}
+
+impl Error for MipmapError {}
+
+pub fn truncate_mipmaps(nutexb: &mut NutexbFile, count: u32) -> Result<(), MipmapError> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    check_format(format).map_err(|_| MipmapError::UnsupportedFormat(format))?;
+
+    if count == 0 || count > footer.mipmap_count {
+        return Err(MipmapError::InvalidMipmapCount {
+            mipmap_count: count,
+            max_mipmap_count: footer.mipmap_count,
+        });
+    }
+
+    // Swizzled layers are aligned based on the size of all their mipmaps,
+    // so the remaining mipmaps may need to move even though their data doesn't change.
+    let is_swizzled = footer.unk3 == 0x1000;
+    let layout = footer.layout();
+    let new_layout = SurfaceLayout {
+        mipmap_count: count,
+        ..layout
+    };
+    let (old_size, size) = if is_swizzled {
+        (layout.swizzled_size()?, new_layout.swizzled_size()?)
+    } else {
+        (layout.deswizzled_size()?, new_layout.deswizzled_size()?)
+    };
+    if nutexb.data.len() < old_size {
+        return Err(tegra_swizzle::SwizzleError::NotEnoughData {
+            expected_size: old_size,
+            actual_size: nutexb.data.len(),
+        }
+        .into());
+    }
+
+    let mut data = vec![0u8; size];
+    for layer in 0..footer.layer_count {
+        for mip in 0..count {
+            let (Some(old), Some(new)) = (layout.mip(layer, mip), new_layout.mip(layer, mip))
+            else {
+                continue;
+            };
+            data[new.data_range(is_swizzled)]
+                .copy_from_slice(&nutexb.data[old.data_range(is_swizzled)]);
+        }
+    }
+
+    nutexb.layer_mipmaps = calculate_layer_mip_sizes(
+        footer.width,
+        footer.height,
+        footer.depth,
+        format.block_dim(),
+        format.bytes_per_pixel(),
+        count,
+        footer.layer_count,
+    );
+    nutexb.data = data;
+    nutexb.footer.mipmap_count = count;
+    nutexb.footer.data_size = nutexb.data.len() as u32;
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+pub fn regenerate_mipmaps(
+    nutexb: &mut NutexbFile,
+    count: u32,
+    filter: image::imageops::FilterType,
+) -> Result<(), MipmapError> {
+    use crate::{
+        convert::{create_nutexb_from_linear, create_nutexb_from_swizzled},
+        swizzle::{deswizzle_surface, swizzle_surface},
+        validate::max_mipmap_count,
+        Surface, MAX_MIPMAP_COUNT,
+    };
+
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+
+    check_filter_format(format)?;
+
+    if footer.depth > 1 {
+        return Err(MipmapError::UnsupportedDepth(footer.depth));
+    }
+
+    let max_mipmap_count = max_mipmap_count(nutexb).min(MAX_MIPMAP_COUNT);
+    if count == 0 || count > max_mipmap_count {
+        return Err(MipmapError::InvalidMipmapCount {
+            mipmap_count: count,
+            max_mipmap_count,
+        });
+    }
+
+    let layout = footer.layout();
+    let size = layout.deswizzled_size()?;
+    let data = if footer.unk3 == 0x1000 {
+        deswizzle_surface(layout, &nutexb.data)?
+    } else {
+        nutexb
+            .data
+            .get(..size)
+            .ok_or(tegra_swizzle::SwizzleError::NotEnoughData {
+                expected_size: size,
+                actual_size: nutexb.data.len(),
+            })?
+            .to_vec()
+    };
+
+    // Generate each mipmap from the base level of each layer to avoid accumulating errors.
+    let (width, height) = (footer.width, footer.height);
+    let mut image_data = Vec::new();
+    for layer in 0..footer.layer_count {
+        let Some(base) = layout.mip(layer, 0) else {
+            continue;
+        };
+        let rgba = to_rgba_f32(format, width, height, &data[base.data_range(false)])?;
+        let image = image::Rgba32FImage::from_raw(width, height, rgba).unwrap();
+
+        for mip in 0..count {
+            let mip_width = (width >> mip).max(1);
+            let mip_height = (height >> mip).max(1);
+            let mip_image = image::imageops::resize(&image, mip_width, mip_height, filter);
+            image_data.extend(from_rgba_f32(
+                format,
+                mip_width,
+                mip_height,
+                mip_image.as_raw(),
+            )?);
+        }
+    }
+
+    let surface = Surface {
+        width,
+        height,
+        depth: 1,
+        image_data,
+        mipmap_count: count,
+        layer_count: footer.layer_count,
+        image_format: format,
+    };
+    *nutexb = if footer.unk3 == 0x1000 {
+        let image_data = swizzle_surface(surface.layout(), &surface.image_data)?;
+        create_nutexb_from_swizzled(
+            Surface {
+                image_data,
+                ..surface
+            },
+            footer.name.clone(),
+        )
+    } else {
+        create_nutexb_from_linear(surface, footer.name.clone())
+    };
+    Ok(())
+}
+
+/// Checks if the pixels for `format` can be converted to and from [to_rgba_f32].
+#[cfg(feature = "image")]
+fn check_filter_format(format: NutexbFormat) -> Result<(), MipmapError> {
+    match format {
+        NutexbFormat::R8Unorm
+        | NutexbFormat::R8G8B8A8Unorm
+        | NutexbFormat::R8G8B8A8Srgb
+        | NutexbFormat::B8G8R8A8Unorm
+        | NutexbFormat::B8G8R8A8Srgb
+        | NutexbFormat::R32G32B32A32Float => Ok(()),
+        NutexbFormat::BC1Unorm
+        | NutexbFormat::BC1Srgb
+        | NutexbFormat::BC2Unorm
+        | NutexbFormat::BC2Srgb
+        | NutexbFormat::BC3Unorm
+        | NutexbFormat::BC3Srgb
+        | NutexbFormat::BC4Unorm
+        | NutexbFormat::BC4Snorm
+        | NutexbFormat::BC5Unorm
+        | NutexbFormat::BC5Snorm
+        | NutexbFormat::BC6Ufloat
+        | NutexbFormat::BC6Sfloat
+        | NutexbFormat::BC7Unorm
+        | NutexbFormat::BC7Srgb => {
+            // Compressed formats need to be encoded again after filtering.
+            if cfg!(feature = "encoder") {
+                Ok(())
+            } else {
+                Err(MipmapError::CannotReencode(format))
+            }
+        }
+        _ => Err(MipmapError::UnsupportedFormat(format)),
+    }
+}
+
+/// Converts pixels to RGBA with linear color channels for filtering.
+#[cfg(feature = "image")]
+#[cfg_attr(not(feature = "encoder"), allow(unused_variables))]
+fn to_rgba_f32(
+    format: NutexbFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<Vec<f32>, MipmapError> {
+    if format == NutexbFormat::R32G32B32A32Float {
+        return Ok(data
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect());
+    }
+
+    #[cfg(feature = "encoder")]
+    if let Some(image_format) = crate::decode::bc_image_format(format) {
+        let mut rgba = image_dds::Surface {
+            width,
+            height,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format,
+            data,
+        }
+        .decode_rgbaf32()?
+        .data;
+        if format.is_srgb() {
+            for pixel in rgba.chunks_exact_mut(4) {
+                for value in &mut pixel[..3] {
+                    *value = crate::decode::srgb_to_linear(*value);
+                }
+            }
+        }
+        return Ok(rgba);
+    }
+
+    let channels = format.channel_count() as usize;
+    Ok(data
+        .chunks_exact(channels)
+        .flat_map(|pixel| {
+            let mut rgba = [0.0, 0.0, 0.0, 1.0];
+            for (i, value) in pixel.iter().enumerate() {
+                rgba[i] = *value as f32 / 255.0;
+            }
+            if format.is_srgb() {
+                for value in &mut rgba[..3] {
+                    *value = crate::decode::srgb_to_linear(*value);
+                }
+            }
+            rgba
+        })
+        .collect())
+}
+
+/// Converts RGBA pixels from [to_rgba_f32] back to the bytes for `format`.
+#[cfg(feature = "image")]
+#[cfg_attr(not(feature = "encoder"), allow(unused_variables))]
+fn from_rgba_f32(
+    format: NutexbFormat,
+    width: u32,
+    height: u32,
+    rgba: &[f32],
+) -> Result<Vec<u8>, MipmapError> {
+    if format == NutexbFormat::R32G32B32A32Float {
+        return Ok(rgba.iter().flat_map(|value| value.to_le_bytes()).collect());
+    }
+
+    #[cfg(feature = "encoder")]
+    if let Some(image_format) = crate::decode::bc_image_format(format) {
+        use image_dds::{Mipmaps, Quality, SurfaceRgba32Float, SurfaceRgba8};
+
+        let surface = match format {
+            // Signed and float formats are encoded from floats to preserve their range.
+            NutexbFormat::BC4Snorm
+            | NutexbFormat::BC5Snorm
+            | NutexbFormat::BC6Ufloat
+            | NutexbFormat::BC6Sfloat => SurfaceRgba32Float {
+                width,
+                height,
+                depth: 1,
+                layers: 1,
+                mipmaps: 1,
+                data: rgba,
+            }
+            .encode(image_format, Quality::Normal, Mipmaps::Disabled)?,
+            _ => SurfaceRgba8 {
+                width,
+                height,
+                depth: 1,
+                layers: 1,
+                mipmaps: 1,
+                data: to_unorm8(format, rgba),
+            }
+            .encode(image_format, Quality::Normal, Mipmaps::Disabled)?,
+        };
+        return Ok(surface.data);
+    }
+
+    let channels = format.channel_count() as usize;
+    Ok(rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| to_unorm8(format, &pixel[..channels]))
+        .collect())
+}
+
+/// Converts the channels from [to_rgba_f32] to 8-bit unorm with sRGB encoding for sRGB formats.
+#[cfg(feature = "image")]
+fn to_unorm8(format: NutexbFormat, values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            if format.is_srgb() && i % 4 < 3 {
+                crate::decode::unorm8(crate::decode::linear_to_srgb(*value))
+            } else {
+                crate::decode::unorm8(*value)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Surface;
+
+    fn rgba8_nutexb(mipmap_count: u32) -> NutexbFile {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![128u8; (4 * 4 + 2 * 2 + 1) * 4],
+            mipmap_count,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        NutexbFile::from_surface(surface, "tex").unwrap()
+    }
+
+    #[test]
+    fn truncate_mipmaps_not_enough_data() {
+        let mut nutexb = rgba8_nutexb(3);
+        nutexb.data.truncate(16);
+        assert!(matches!(
+            nutexb.truncate_mipmaps(2),
+            Err(MipmapError::SwizzleError(
+                tegra_swizzle::SwizzleError::NotEnoughData { .. }
+            ))
+        ));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn regenerate_mipmaps_unsupported_format() {
+        let mut nutexb = rgba8_nutexb(1);
+        nutexb.footer.image_format = NutexbFormat::R16G16B16A16Float;
+        assert!(matches!(
+            nutexb.regenerate_mipmaps(2, image::imageops::FilterType::Triangle),
+            Err(MipmapError::UnsupportedFormat(
+                NutexbFormat::R16G16B16A16Float
+            ))
+        ));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn regenerate_mipmaps_name_without_null_terminator() {
+        use binrw::BinReaderExt;
+
+        // Names read from files may fill the entire field without a null terminator.
+        let name: crate::TextureName = std::io::Cursor::new([b'a'; 64]).read_le().unwrap();
+        let mut nutexb = rgba8_nutexb(1);
+        nutexb.footer.name = name.clone();
+
+        nutexb
+            .regenerate_mipmaps(3, image::imageops::FilterType::Triangle)
+            .unwrap();
+        assert_eq!(3, nutexb.footer.mipmap_count);
+        assert_eq!(name, nutexb.footer.name);
+    }
+
+    #[cfg(all(feature = "image", not(feature = "encoder")))]
+    #[test]
+    fn regenerate_mipmaps_bc_without_encoder() {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![0u8; 16],
+            mipmap_count: 1,
+            layer_count: 1,
+            image_format: NutexbFormat::BC7Unorm,
+        };
+        let mut nutexb = NutexbFile::from_surface(surface, "tex").unwrap();
+        assert!(matches!(
+            nutexb.regenerate_mipmaps(3, image::imageops::FilterType::Triangle),
+            Err(MipmapError::CannotReencode(NutexbFormat::BC7Unorm))
+        ));
+    }
+
+    #[cfg(feature = "encoder")]
+    fn check_regenerate_bc(format: NutexbFormat, width: u32, height: u32, rgba: [u8; 4]) {
+        use image_dds::{Mipmaps, Quality, SurfaceRgba8};
+
+        let image_format = crate::decode::bc_image_format(format).unwrap();
+        let layer_count = 2;
+        let base = SurfaceRgba8 {
+            width,
+            height,
+            depth: 1,
+            layers: layer_count,
+            mipmaps: 1,
+            data: rgba.repeat((width * height * layer_count) as usize),
+        }
+        .encode(image_format, Quality::Normal, Mipmaps::Disabled)
+        .unwrap();
+
+        let surface = Surface {
+            width,
+            height,
+            depth: 1,
+            image_data: base.data,
+            mipmap_count: 1,
+            layer_count,
+            image_format: format,
+        };
+        let mut nutexb = NutexbFile::from_surface(surface, "tex").unwrap();
+
+        let count = crate::validate::max_mipmap_count(&nutexb);
+        nutexb
+            .regenerate_mipmaps(count, image::imageops::FilterType::Triangle)
+            .unwrap();
+        assert_eq!(count, nutexb.footer.mipmap_count);
+        assert!(nutexb.validate().is_empty());
+
+        // Filtering a solid color doesn't change the color after the initial compression.
+        let decoded = image_dds::Surface {
+            width,
+            height,
+            depth: 1,
+            layers: layer_count,
+            mipmaps: count,
+            image_format,
+            data: nutexb.deswizzled_data().unwrap(),
+        }
+        .decode_rgba8()
+        .unwrap();
+        let expected = &decoded.data[..4];
+        for pixel in decoded.data.chunks_exact(4) {
+            let matches = pixel.iter().zip(expected).all(|(a, b)| a.abs_diff(*b) <= 1);
+            assert!(matches, "{pixel:?} != {expected:?}");
+        }
+        assert!(expected.iter().zip(rgba).all(|(a, b)| a.abs_diff(b) <= 4));
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn regenerate_mipmaps_bc1() {
+        check_regenerate_bc(NutexbFormat::BC1Unorm, 20, 12, [64, 128, 192, 255]);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn regenerate_mipmaps_bc3_srgb() {
+        check_regenerate_bc(NutexbFormat::BC3Srgb, 16, 16, [200, 100, 50, 128]);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn regenerate_mipmaps_bc7_srgb() {
+        check_regenerate_bc(NutexbFormat::BC7Srgb, 36, 20, [128, 128, 128, 255]);
+    }
+}
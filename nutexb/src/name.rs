@@ -0,0 +1,319 @@
+use std::{error::Error, fmt::Display, ops::Deref};
+
+use binrw::{BinRead, BinResult, BinWrite};
+
+/// The name of a texture stored in the nutexb footer like `"def_001_col"`.
+///
+/// Names are UTF-8 with at most [TextureName::MAX_LEN] bytes
+/// to leave room for the null terminator in the 64 byte name field.
+/// Names read from files are not validated, so they may fill the entire field
+/// or contain invalid UTF-8. The original bytes are preserved when writing.
+///
+/// # Examples
+/**
+```rust
+use nutexb::{TextureName, TextureNameError};
+
+let name = TextureName::new("def_001_col").unwrap();
+assert_eq!("def_001_col", name.as_str());
+
+let long_name = "a".repeat(64);
+assert_eq!(
+    Err(TextureNameError::TooLong { length: 64 }),
+    TextureName::new(long_name.clone())
+);
+assert_eq!(63, TextureName::truncated(long_name).len());
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureName {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+/// Errors for names that can't be stored in the nutexb footer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextureNameError {
+    /// The name is longer than [TextureName::MAX_LEN] bytes.
+    TooLong { length: usize },
+    /// The name contains a null byte, which would end the name early.
+    ContainsNull,
+}
+
+impl Display for TextureNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureNameError::TooLong { length } => write!(
+                f,
+                "texture name length {length} exceeds the maximum of {} bytes",
+                TextureName::MAX_LEN
+            ),
+            TextureNameError::ContainsNull => write!(f, "texture name contains a null byte"),
+        }
+    }
+}
+
+impl Error for TextureNameError {}
+
+// The name is stored in a fixed size field in the footer.
+const FIELD_SIZE: usize = 0x40;
+
+impl TextureName {
+    /// The maximum length of the name in bytes without the null terminator.
+    pub const MAX_LEN: usize = FIELD_SIZE - 1;
+
+    fn from_string(name: String) -> Self {
+        Self {
+            bytes: name.as_bytes().to_vec(),
+            name,
+        }
+    }
+
+    /// Creates a new name or returns an error if `name` does not fit in the footer.
+    pub fn new<S: Into<String>>(name: S) -> Result<Self, TextureNameError> {
+        let name = name.into();
+        if name.len() > Self::MAX_LEN {
+            Err(TextureNameError::TooLong { length: name.len() })
+        } else if name.contains('\0') {
+            Err(TextureNameError::ContainsNull)
+        } else {
+            Ok(Self::from_string(name))
+        }
+    }
+
+    /// Creates a new name by removing anything after the first null byte
+    /// and truncating to [TextureName::MAX_LEN] bytes without splitting characters.
+    pub fn truncated<S: Into<String>>(name: S) -> Self {
+        let mut name = name.into();
+        if let Some(null) = name.find('\0') {
+            name.truncate(null);
+        }
+
+        let mut length = name.len().min(Self::MAX_LEN);
+        while !name.is_char_boundary(length) {
+            length -= 1;
+        }
+        name.truncate(length);
+
+        Self::from_string(name)
+    }
+
+    /// The name as a string slice.
+    /// Invalid UTF-8 in names read from files is replaced with `U+FFFD`.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// The bytes of the name without the null terminator as stored in the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Deref for TextureName {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+
+impl AsRef<str> for TextureName {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Display for TextureName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl From<TextureName> for String {
+    fn from(value: TextureName) -> Self {
+        value.name
+    }
+}
+
+impl TryFrom<String> for TextureName {
+    type Error = TextureNameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<&str> for TextureName {
+    type Error = TextureNameError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+// Names are read without validation to support reading and writing any file.
+impl BinRead for TextureName {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        endian: binrw::Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        // Stop at the end of the field for names without a null terminator.
+        let mut bytes = Vec::new();
+        while bytes.len() < FIELD_SIZE {
+            match u8::read_options(reader, endian, args)? {
+                0 => break,
+                b => bytes.push(b),
+            }
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(&bytes).into_owned(),
+            bytes,
+        })
+    }
+}
+
+impl BinWrite for TextureName {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        writer.write_all(&self.bytes)?;
+        if self.bytes.len() < FIELD_SIZE {
+            writer.write_all(&[0])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::BinReaderExt;
+
+    use super::*;
+    use crate::{NutexbFile, NutexbFormat, Surface, ValidationIssue, WriteMode};
+
+    fn nutexb_bytes(name: &[u8]) -> Vec<u8> {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![0u8; 4 * 4 * 4],
+            mipmap_count: 1,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        let nutexb = NutexbFile::from_surface(surface, "tex").unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        nutexb.write(&mut writer).unwrap();
+        let mut bytes = writer.into_inner();
+
+        // Replace the name field after the footer magic.
+        let start = bytes.len() - 112 + 4;
+        bytes[start..start + FIELD_SIZE].fill(0);
+        bytes[start..start + name.len()].copy_from_slice(name);
+        bytes
+    }
+
+    #[test]
+    fn read_name() {
+        let name: TextureName = Cursor::new(b"def_001_col\0").read_le().unwrap();
+        assert_eq!("def_001_col", name.as_str());
+        assert_eq!(b"def_001_col", name.as_bytes());
+    }
+
+    #[test]
+    fn read_name_without_null_terminator() {
+        let bytes = [b'a'; FIELD_SIZE + 4];
+        let mut reader = Cursor::new(&bytes);
+        let name: TextureName = reader.read_le().unwrap();
+        assert_eq!(FIELD_SIZE, name.len());
+        assert_eq!(FIELD_SIZE as u64, reader.position());
+    }
+
+    #[test]
+    fn read_name_invalid_utf8() {
+        let name: TextureName = Cursor::new(b"tex_\xFF\xFE\0").read_le().unwrap();
+        assert_eq!("tex_\u{FFFD}\u{FFFD}", name.as_str());
+        assert_eq!(b"tex_\xFF\xFE", name.as_bytes());
+    }
+
+    #[test]
+    fn round_trip_name_without_null_terminator() {
+        let bytes = nutexb_bytes(&[b'a'; FIELD_SIZE]);
+        let nutexb = NutexbFile::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!("a".repeat(FIELD_SIZE), nutexb.footer.name());
+        assert_eq!(4, nutexb.footer.width);
+
+        let mut writer = Cursor::new(Vec::new());
+        nutexb.write(&mut writer).unwrap();
+        assert_eq!(bytes, writer.into_inner());
+    }
+
+    #[test]
+    fn convert_layout_name_without_null_terminator() {
+        let name = [b'a'; FIELD_SIZE];
+        let nutexb = NutexbFile::read(&mut Cursor::new(nutexb_bytes(&name))).unwrap();
+
+        let linear = nutexb.to_linear().unwrap();
+        assert_eq!(&name, linear.footer.name.as_bytes());
+
+        let swizzled = linear.to_swizzled().unwrap();
+        assert_eq!(&name, swizzled.footer.name.as_bytes());
+        assert_eq!(nutexb.data, swizzled.data);
+    }
+
+    #[test]
+    fn round_trip_name_invalid_utf8() {
+        let mut bytes = nutexb_bytes(b"tex_\xFF");
+        // Add padding after the null terminator.
+        let padding = bytes.len() - 112 + 4 + 6;
+        bytes[padding] = 1;
+
+        let nutexb = NutexbFile::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!("tex_\u{FFFD}", nutexb.footer.name());
+
+        let mut writer = Cursor::new(Vec::new());
+        nutexb
+            .write_with_mode(&mut writer, WriteMode::RoundTrip)
+            .unwrap();
+        assert_eq!(bytes, writer.into_inner());
+    }
+
+    #[test]
+    fn repair_name_without_null_terminator() {
+        let mut nutexb =
+            NutexbFile::read(&mut Cursor::new(nutexb_bytes(&[b'a'; FIELD_SIZE]))).unwrap();
+        assert_eq!(
+            vec![ValidationIssue::NameTooLong { length: FIELD_SIZE }],
+            nutexb.validate()
+        );
+
+        assert!(nutexb.repair().is_empty());
+        assert_eq!("a".repeat(TextureName::MAX_LEN), nutexb.footer.name());
+    }
+
+    #[test]
+    fn set_name_validates() {
+        let mut nutexb = NutexbFile::read(&mut Cursor::new(nutexb_bytes(b"tex"))).unwrap();
+        assert_eq!(
+            Err(TextureNameError::TooLong { length: 64 }),
+            nutexb.footer.set_name("a".repeat(64))
+        );
+        assert_eq!(
+            Err(TextureNameError::ContainsNull),
+            nutexb.footer.set_name("a\0b")
+        );
+        nutexb.footer.set_name("def_001_col").unwrap();
+        assert_eq!(b"def_001_col", nutexb.footer.name.as_bytes());
+    }
+}
@@ -0,0 +1,386 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+use tegra_swizzle::{div_round_up, swizzle::swizzle_block_linear};
+
+use crate::{convert::check_format, swizzle::Mip, NutexbFile, NutexbFormat};
+
+/// A rectangular region of a mipmap in pixels.
+///
+/// Regions for compressed formats must be aligned to the block dimensions like 4x4 pixels for BC7.
+/// The right and bottom edges may also end at the edges of the mipmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Errors while reading or writing a [Rect] of image data.
+#[derive(Debug)]
+pub enum RegionError {
+    /// The image format is [NutexbFormat::Unknown], so the size and layout of the data isn't known.
+    UnsupportedFormat(NutexbFormat),
+    /// The layer or mipmap is out of range for the layer and mipmap counts in the footer.
+    InvalidSubresource { layer: u32, mip: u32 },
+    /// The region extends past the edges of the mipmap.
+    OutOfBounds { rect: Rect, width: u32, height: u32 },
+    /// The region does not start or end on a block boundary for a compressed format.
+    UnalignedRect {
+        rect: Rect,
+        block_width: u32,
+        block_height: u32,
+    },
+    /// The image data or region data has fewer bytes than required.
+    NotEnoughData {
+        expected_size: usize,
+        actual_size: usize,
+    },
+    /// The data for an entire mipmap does not have the same size as the mipmap.
+    DataSizeMismatch {
+        expected_size: usize,
+        actual_size: usize,
+    },
+}
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegionError::UnsupportedFormat(format) => {
+                write!(f, "unsupported image format 0x{:X}", u32::from(*format))
+            }
+            RegionError::InvalidSubresource { layer, mip } => {
+                write!(f, "layer {layer} mipmap {mip} is out of range")
+            }
+            RegionError::OutOfBounds {
+                rect,
+                width,
+                height,
+            } => write!(f, "{rect:?} is out of bounds for a {width}x{height} mipmap"),
+            RegionError::UnalignedRect {
+                rect,
+                block_width,
+                block_height,
+            } => write!(
+                f,
+                "{rect:?} is not aligned to {block_width}x{block_height} pixel blocks"
+            ),
+            RegionError::NotEnoughData {
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "expected at least {expected_size} bytes but found {actual_size} bytes"
+            ),
+            RegionError::DataSizeMismatch {
+                expected_size,
+                actual_size,
+            } => write!(
+                f,
+                "expected {expected_size} bytes but found {actual_size} bytes"
+            ),
+        }
+    }
+}
+
+impl Error for RegionError {}
+
+/// The blocks covered by a [Rect] for a single mipmap of a single layer.
+struct Region {
+    mip: Mip,
+    is_swizzled: bool,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Region {
+    fn new(nutexb: &NutexbFile, layer: u32, mip: u32, rect: Rect) -> Result<Self, RegionError> {
+        let footer = &nutexb.footer;
+        let format = footer.image_format;
+        check_format(format).map_err(|_| RegionError::UnsupportedFormat(format))?;
+
+        let layout = footer.layout();
+        let mip_layout = layout
+            .mip(layer, mip)
+            .ok_or(RegionError::InvalidSubresource { layer, mip })?;
+
+        // Compare in pixels to allow partial blocks along the edges of the mipmap.
+        let width = (footer.width >> mip).max(1);
+        let height = (footer.height >> mip).max(1);
+        let right = rect.x.checked_add(rect.width);
+        let bottom = rect.y.checked_add(rect.height);
+        let (Some(right), Some(bottom)) = (right, bottom) else {
+            return Err(RegionError::OutOfBounds {
+                rect,
+                width,
+                height,
+            });
+        };
+        if right > width || bottom > height {
+            return Err(RegionError::OutOfBounds {
+                rect,
+                width,
+                height,
+            });
+        }
+
+        let block_width = format.block_width();
+        let block_height = format.block_height();
+        if !rect.x.is_multiple_of(block_width)
+            || !rect.y.is_multiple_of(block_height)
+            || !(right.is_multiple_of(block_width) || right == width)
+            || !(bottom.is_multiple_of(block_height) || bottom == height)
+        {
+            return Err(RegionError::UnalignedRect {
+                rect,
+                block_width,
+                block_height,
+            });
+        }
+
+        let region = Self {
+            is_swizzled: footer.unk3 == 0x1000,
+            x: rect.x / block_width,
+            y: rect.y / block_height,
+            width: div_round_up(right, block_width) - rect.x / block_width,
+            height: div_round_up(bottom, block_height) - rect.y / block_height,
+            mip: mip_layout,
+        };
+
+        // Check the entire mipmap, so the offsets for each block are in range.
+        let end = region.mip.data_range(region.is_swizzled).end;
+        if nutexb.data.len() < end {
+            return Err(RegionError::NotEnoughData {
+                expected_size: end,
+                actual_size: nutexb.data.len(),
+            });
+        }
+
+        Ok(region)
+    }
+
+    /// The size in bytes of the blocks in the region without any padding.
+    fn size(&self) -> usize {
+        self.width as usize
+            * self.height as usize
+            * self.mip.depth as usize
+            * self.mip.bytes_per_pixel as usize
+    }
+
+    /// Calls `f` with the range in the image data and offset in the region data
+    /// for each contiguous span of bytes in the region.
+    fn for_each_span<F: FnMut(Range<usize>, usize)>(&self, mut f: F) {
+        let bytes_per_pixel = self.mip.bytes_per_pixel as usize;
+        let row_size = self.width as usize * bytes_per_pixel;
+
+        let mut offset = 0;
+        for z in 0..self.mip.depth {
+            for y in self.y..self.y + self.height {
+                if self.is_swizzled {
+                    // Blocks are contiguous, but adjacent blocks may not be.
+                    for x in self.x..self.x + self.width {
+                        let start = self.mip.swizzled_block_offset(x, y, z);
+                        f(start..start + bytes_per_pixel, offset);
+                        offset += bytes_per_pixel;
+                    }
+                } else {
+                    let start = self.mip.deswizzled_block_offset(self.x, y, z);
+                    f(start..start + row_size, offset);
+                    offset += row_size;
+                }
+            }
+        }
+    }
+}
+
+pub fn read_region(
+    nutexb: &NutexbFile,
+    layer: u32,
+    mip: u32,
+    rect: Rect,
+) -> Result<Vec<u8>, RegionError> {
+    let region = Region::new(nutexb, layer, mip, rect)?;
+
+    let mut output = vec![0u8; region.size()];
+    region.for_each_span(|range, offset| {
+        output[offset..offset + range.len()].copy_from_slice(&nutexb.data[range]);
+    });
+    Ok(output)
+}
+
+pub fn write_region(
+    nutexb: &mut NutexbFile,
+    layer: u32,
+    mip: u32,
+    rect: Rect,
+    data: &[u8],
+) -> Result<(), RegionError> {
+    let region = Region::new(nutexb, layer, mip, rect)?;
+
+    let size = region.size();
+    if data.len() < size {
+        return Err(RegionError::NotEnoughData {
+            expected_size: size,
+            actual_size: data.len(),
+        });
+    }
+
+    region.for_each_span(|range, offset| {
+        let len = range.len();
+        nutexb.data[range].copy_from_slice(&data[offset..offset + len]);
+    });
+    Ok(())
+}
+
+pub fn replace_subresource(
+    nutexb: &mut NutexbFile,
+    layer: u32,
+    mip: u32,
+    data: &[u8],
+) -> Result<(), RegionError> {
+    // Invalid mipmaps are checked when creating the region.
+    let rect = Rect {
+        x: 0,
+        y: 0,
+        width: nutexb.footer.width.checked_shr(mip).unwrap_or(0).max(1),
+        height: nutexb.footer.height.checked_shr(mip).unwrap_or(0).max(1),
+    };
+    let region = Region::new(nutexb, layer, mip, rect)?;
+
+    let size = region.size();
+    if data.len() != size {
+        return Err(RegionError::DataSizeMismatch {
+            expected_size: size,
+            actual_size: data.len(),
+        });
+    }
+
+    let mip = &region.mip;
+    let range = mip.data_range(region.is_swizzled);
+    if !region.is_swizzled {
+        nutexb.data[range].copy_from_slice(data);
+    } else if mip.depth == 1 {
+        // The only possible error is not enough data, which was checked above.
+        let swizzled = swizzle_block_linear(
+            mip.width,
+            mip.height,
+            1,
+            data,
+            mip.block_height,
+            mip.bytes_per_pixel,
+        )
+        .map_err(|_| RegionError::NotEnoughData {
+            expected_size: size,
+            actual_size: data.len(),
+        })?;
+        nutexb.data[range].copy_from_slice(&swizzled);
+    } else {
+        // 3D mipmaps use a block depth not supported by the per mipmap functions.
+        region.for_each_span(|range, offset| {
+            let len = range.len();
+            nutexb.data[range].copy_from_slice(&data[offset..offset + len]);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Surface;
+
+    fn data(size: usize, seed: usize) -> Vec<u8> {
+        (0..size).map(|i| (i * 7 + i / 251 + seed) as u8).collect()
+    }
+
+    fn check_replace_subresource(surface: Surface<Vec<u8>>, layer: u32, mip: u32) {
+        let mut nutexb = NutexbFile::from_surface(surface.clone(), "tex").unwrap();
+
+        // Replace the mipmap in the untiled surface and swizzle everything.
+        let range = nutexb
+            .footer
+            .layout()
+            .mip(layer, mip)
+            .unwrap()
+            .data_range(false);
+        let mip_data = data(range.len(), 1);
+        let mut expected = surface;
+        expected.image_data[range].copy_from_slice(&mip_data);
+        let expected = NutexbFile::from_surface(expected, "tex").unwrap();
+
+        nutexb.replace_subresource(layer, mip, &mip_data).unwrap();
+        assert!(expected.data == nutexb.data);
+    }
+
+    #[test]
+    fn replace_subresource_rgba8_layers() {
+        let surface = Surface {
+            width: 64,
+            height: 64,
+            depth: 1,
+            image_data: data((64 * 64 + 32 * 32 + 16 * 16) * 4 * 6, 0),
+            mipmap_count: 3,
+            layer_count: 6,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        check_replace_subresource(surface.clone(), 0, 0);
+        check_replace_subresource(surface, 4, 2);
+    }
+
+    #[test]
+    fn replace_subresource_bc7_odd_mipmap() {
+        // The mipmaps are 20x12, 10x6, 5x3 pixels or 5x3, 3x2, 2x1 blocks.
+        let surface = Surface {
+            width: 20,
+            height: 12,
+            depth: 1,
+            image_data: data((5 * 3 + 3 * 2 + 2) * 16 * 2, 0),
+            mipmap_count: 3,
+            layer_count: 2,
+            image_format: NutexbFormat::BC7Unorm,
+        };
+        check_replace_subresource(surface.clone(), 1, 1);
+        check_replace_subresource(surface, 1, 2);
+    }
+
+    #[test]
+    fn replace_subresource_3d() {
+        let surface = Surface {
+            width: 16,
+            height: 16,
+            depth: 16,
+            image_data: data((16 * 16 * 16 + 8 * 8 * 8) * 4, 0),
+            mipmap_count: 2,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        check_replace_subresource(surface.clone(), 0, 0);
+        check_replace_subresource(surface, 0, 1);
+    }
+
+    #[test]
+    fn replace_subresource_data_size_mismatch() {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            image_data: vec![0u8; 4 * 4 * 4],
+            mipmap_count: 1,
+            layer_count: 1,
+            image_format: NutexbFormat::R8G8B8A8Unorm,
+        };
+        let mut nutexb = NutexbFile::from_surface(surface, "tex").unwrap();
+
+        for size in [4 * 4 * 4 - 1, 4 * 4 * 4 + 1] {
+            assert!(matches!(
+                nutexb.replace_subresource(0, 0, &vec![0u8; size]),
+                Err(RegionError::DataSizeMismatch {
+                    expected_size: 64,
+                    actual_size
+                }) if actual_size == size
+            ));
+        }
+    }
+}
@@ -0,0 +1,698 @@
+//! Surface swizzling into caller provided buffers.
+//!
+//! Each mipmap is copied directly between the tiled and untiled buffers
+//! using the same addresses and offsets as [tegra_swizzle::surface], so the output is identical.
+//! The `"rayon"` feature processes array layers and mipmaps in parallel.
+use std::ops::Range;
+
+use tegra_swizzle::{
+    block_height_mip0, div_round_up, mip_block_height,
+    surface::{deswizzled_surface_size, swizzled_surface_size, BlockDim},
+    swizzle::{deswizzle_block_linear, deswizzled_mip_size, swizzled_mip_size},
+    BlockHeight, SwizzleError,
+};
+
+use crate::convert::is_valid_surface;
+
+/// Options for how image data is swizzled and deswizzled.
+///
+/// Files created by this library and most files from Smash Ultimate use the default options.
+///
+/// # Examples
+/**
+```rust no_run
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+use nutexb::{BlockHeight, NutexbFile, SwizzleOptions};
+
+let nutexb = NutexbFile::read_from_file("col_001.nutexb")?;
+
+let options = SwizzleOptions {
+    block_height: Some(BlockHeight::Eight),
+};
+let surface_data = nutexb.deswizzled_data_with_options(options)?;
+# Ok(()) }
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SwizzleOptions {
+    /// The block height in GOBs for the base mipmap
+    /// or [None] to infer the block height from the dimensions and format.
+    ///
+    /// The block height is ignored for 3D textures, which always use [BlockHeight::One].
+    pub block_height: Option<BlockHeight>,
+}
+
+/// The dimensions and format of a surface for swizzling or deswizzling.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceLayout {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub block_dim: BlockDim,
+    pub block_height_mip0: Option<BlockHeight>,
+    pub bytes_per_pixel: u32,
+    pub mipmap_count: u32,
+    pub layer_count: u32,
+}
+
+impl SurfaceLayout {
+    fn is_empty(&self) -> bool {
+        self.width == 0
+            || self.height == 0
+            || self.depth == 0
+            || self.bytes_per_pixel == 0
+            || self.mipmap_count == 0
+            || self.layer_count == 0
+    }
+
+    /// Checks for overflow with the same conditions as [tegra_swizzle::surface].
+    fn validate(&self) -> Result<(), SwizzleError> {
+        if self.is_empty()
+            || is_valid_surface(
+                self.width,
+                self.height,
+                self.depth,
+                self.bytes_per_pixel,
+                self.mipmap_count,
+            )
+        {
+            Ok(())
+        } else {
+            Err(SwizzleError::InvalidSurface {
+                width: self.width,
+                height: self.height,
+                depth: self.depth,
+                bytes_per_pixel: self.bytes_per_pixel,
+                mipmap_count: self.mipmap_count,
+            })
+        }
+    }
+
+    /// The size in bytes of the tiled data or `0` for empty surfaces.
+    pub fn swizzled_size(&self) -> Result<usize, SwizzleError> {
+        self.validate()?;
+        if self.is_empty() {
+            return Ok(0);
+        }
+        Ok(swizzled_surface_size(
+            self.width,
+            self.height,
+            self.depth,
+            self.block_dim,
+            self.block_height_mip0,
+            self.bytes_per_pixel,
+            self.mipmap_count,
+            self.layer_count,
+        ))
+    }
+
+    /// The size in bytes of the untiled data or `0` for empty surfaces.
+    pub fn deswizzled_size(&self) -> Result<usize, SwizzleError> {
+        self.validate()?;
+        if self.is_empty() {
+            return Ok(0);
+        }
+        Ok(deswizzled_surface_size(
+            self.width,
+            self.height,
+            self.depth,
+            self.block_dim,
+            self.bytes_per_pixel,
+            self.mipmap_count,
+            self.layer_count,
+        ))
+    }
+
+    /// The block height for the base mipmap in the same way as [tegra_swizzle::surface].
+    fn resolved_block_height_mip0(&self) -> BlockHeight {
+        if self.depth == 1 {
+            self.block_height_mip0.unwrap_or_else(|| {
+                block_height_mip0(div_round_up(self.height, self.block_dim.height.get()))
+            })
+        } else {
+            BlockHeight::One
+        }
+    }
+
+    /// Calculates the location of each mipmap of each layer in both layouts.
+    pub fn mips(&self) -> Vec<Mip> {
+        (0..self.layer_count)
+            .flat_map(|layer| (0..self.mipmap_count).filter_map(move |mip| self.mip(layer, mip)))
+            .collect()
+    }
+
+    /// Calculates the location of mipmap `mip` of layer `layer` in both layouts
+    /// or `None` if the layer or mipmap is out of range.
+    pub fn mip(&self, layer: u32, mip: u32) -> Option<Mip> {
+        if self.is_empty()
+            || self.validate().is_err()
+            || layer >= self.layer_count
+            || mip >= self.mipmap_count
+        {
+            return None;
+        }
+
+        let block_height_mip0 = self.resolved_block_height_mip0();
+        let block_depth_mip0 = block_depth(self.depth);
+
+        // Layers are aligned in the swizzled layout, so each layer has the same size.
+        let layer = layer as usize;
+        let mut swizzled_offset = layer * (self.swizzled_size().ok()? / self.layer_count as usize);
+        let mut deswizzled_offset =
+            layer * (self.deswizzled_size().ok()? / self.layer_count as usize);
+
+        for i in 0..=mip {
+            let width = div_round_up(self.width >> i, self.block_dim.width.get()).max(1);
+            let height = div_round_up(self.height >> i, self.block_dim.height.get()).max(1);
+            let depth = div_round_up(self.depth >> i, self.block_dim.depth.get()).max(1);
+            let block_height = mip_block_height(height, block_height_mip0);
+
+            let swizzled_size =
+                swizzled_mip_size(width, height, depth, block_height, self.bytes_per_pixel);
+            let deswizzled_size = deswizzled_mip_size(width, height, depth, self.bytes_per_pixel);
+
+            if i == mip {
+                return Some(Mip {
+                    width,
+                    height,
+                    depth,
+                    block_height,
+                    block_depth: mip_block_depth(depth, block_depth_mip0),
+                    bytes_per_pixel: self.bytes_per_pixel,
+                    swizzled_offset,
+                    swizzled_size,
+                    deswizzled_offset,
+                    deswizzled_size,
+                });
+            }
+
+            swizzled_offset += swizzled_size;
+            deswizzled_offset += deswizzled_size;
+        }
+
+        None
+    }
+}
+
+/// A single mipmap of a single layer in block dimensions.
+pub struct Mip {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub block_height: BlockHeight,
+    pub block_depth: u32,
+    pub bytes_per_pixel: u32,
+    pub swizzled_offset: usize,
+    pub swizzled_size: usize,
+    pub deswizzled_offset: usize,
+    pub deswizzled_size: usize,
+}
+
+impl Mip {
+    /// The range of the mipmap in the tiled surface if `is_swizzled` is `true` or the untiled surface otherwise.
+    pub fn data_range(&self, is_swizzled: bool) -> Range<usize> {
+        if is_swizzled {
+            self.swizzled_offset..self.swizzled_offset + self.swizzled_size
+        } else {
+            self.deswizzled_offset..self.deswizzled_offset + self.deswizzled_size
+        }
+    }
+
+    /// Returns `true` if the block coordinates are within the dimensions of the mipmap.
+    pub fn contains(&self, x: u32, y: u32, z: u32) -> bool {
+        x < self.width && y < self.height && z < self.depth
+    }
+
+    /// The offset in bytes of the block at `x`, `y`, `z` in the untiled surface.
+    pub fn deswizzled_block_offset(&self, x: u32, y: u32, z: u32) -> usize {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let index = (z as usize * height + y as usize) * width + x as usize;
+        self.deswizzled_offset + index * self.bytes_per_pixel as usize
+    }
+
+    /// The offset in bytes of the block at `x`, `y`, `z` in the tiled surface.
+    ///
+    /// Blocks are at most 16 bytes and evenly divide the 16 byte rows within a GOB,
+    /// so the bytes for each block are contiguous.
+    pub fn swizzled_block_offset(&self, x: u32, y: u32, z: u32) -> usize {
+        // The address calculations match the ones used by tegra_swizzle
+        // from the examples in the Tegra TRM v1.3 page 1217.
+        let block_height = self.block_height as usize;
+        let block_depth = self.block_depth as usize;
+
+        let width_in_gobs =
+            div_round_up(self.width * self.bytes_per_pixel, GOB_WIDTH_IN_BYTES) as usize;
+        let block_size = GOB_SIZE_IN_BYTES * block_height * block_depth;
+        let block_height_in_bytes = GOB_HEIGHT_IN_BYTES * block_height;
+        let slice_size =
+            (self.height as usize).div_ceil(block_height_in_bytes) * block_size * width_in_gobs;
+
+        // Convert the block coordinates to byte coordinates.
+        let x = x as usize * self.bytes_per_pixel as usize;
+        let y = y as usize;
+        let z = z as usize;
+
+        let offset_z =
+            z / block_depth * slice_size + z % block_depth * GOB_SIZE_IN_BYTES * block_height;
+        let offset_y = y / block_height_in_bytes * block_size * width_in_gobs
+            + y % block_height_in_bytes / GOB_HEIGHT_IN_BYTES * GOB_SIZE_IN_BYTES;
+        let offset_x = x / GOB_WIDTH_IN_BYTES as usize * block_size;
+
+        self.swizzled_offset + offset_z + offset_y + offset_x + gob_offset(x, y)
+    }
+}
+
+const GOB_WIDTH_IN_BYTES: u32 = 64;
+const GOB_HEIGHT_IN_BYTES: usize = 8;
+const GOB_SIZE_IN_BYTES: usize = 512;
+
+/// The offset within a 64x8 byte GOB for the byte at `x`, `y`.
+fn gob_offset(x: usize, y: usize) -> usize {
+    ((x % 64) / 32) * 256 + ((y % 8) / 2) * 64 + ((x % 32) / 16) * 32 + (y % 2) * 16 + (x % 16)
+}
+
+// Block depth code ported from the implementation used by tegra_swizzle.
+// This comes from the Ryujinx emulator: https://github.com/Ryujinx/Ryujinx/blob/master/LICENSE.txt.
+fn block_depth(depth: u32) -> u32 {
+    let depth_and_half = depth + (depth / 2);
+    if depth_and_half >= 16 {
+        16
+    } else if depth_and_half >= 8 {
+        8
+    } else if depth_and_half >= 4 {
+        4
+    } else if depth_and_half >= 2 {
+        2
+    } else {
+        1
+    }
+}
+
+fn mip_block_depth(mip_depth: u32, block_depth: u32) -> u32 {
+    let mut block_depth = block_depth;
+    while mip_depth <= block_depth / 2 && block_depth > 1 {
+        block_depth /= 2;
+    }
+    block_depth
+}
+
+/// Tiles all the layers and mipmaps in `source` like [tegra_swizzle::surface::swizzle_surface].
+pub fn swizzle_surface(layout: SurfaceLayout, source: &[u8]) -> Result<Vec<u8>, SwizzleError> {
+    let mut output = Vec::new();
+    swizzle_surface_into(layout, source, &mut output)?;
+    Ok(output)
+}
+
+/// Tiles all the layers and mipmaps in `source` to `output` after resizing it to the swizzled size.
+/// This reuses the allocation for `output` if it has enough capacity.
+pub fn swizzle_surface_into(
+    layout: SurfaceLayout,
+    source: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), SwizzleError> {
+    let size = layout.swizzled_size()?;
+    check_source(source, layout.deswizzled_size()?)?;
+
+    // Padding between layers is not written, so clear any previous data.
+    output.clear();
+    output.resize(size, 0);
+    swizzle_inner::<false>(layout, source, output);
+    Ok(())
+}
+
+/// Untiles all the layers and mipmaps in `source` like [tegra_swizzle::surface::deswizzle_surface].
+pub fn deswizzle_surface(layout: SurfaceLayout, source: &[u8]) -> Result<Vec<u8>, SwizzleError> {
+    let mut output = vec![0u8; layout.deswizzled_size()?];
+    deswizzle_surface_into(layout, source, &mut output)?;
+    Ok(output)
+}
+
+/// Untiles all the layers and mipmaps in `source` to the start of `output`.
+/// The `output` must have at least as many bytes as [SurfaceLayout::deswizzled_size].
+pub fn deswizzle_surface_into(
+    layout: SurfaceLayout,
+    source: &[u8],
+    output: &mut [u8],
+) -> Result<(), SwizzleError> {
+    let size = layout.deswizzled_size()?;
+    check_source(source, layout.swizzled_size()?)?;
+
+    // The deswizzled layout has no padding, so every byte is overwritten.
+    swizzle_inner::<true>(layout, source, &mut output[..size]);
+    Ok(())
+}
+
+/// Scores each block height by how smooth the base mipmap of the first layer is after deswizzling.
+/// Lower scores are better. Block heights that require more data than `data` are skipped.
+pub fn score_block_heights(layout: SurfaceLayout, data: &[u8]) -> Vec<(BlockHeight, f64)> {
+    if layout.is_empty() || layout.validate().is_err() {
+        return Vec::new();
+    }
+
+    // List the inferred block height first, so it's preferred for ties.
+    let inferred = layout.resolved_block_height_mip0();
+    let mut candidates = vec![inferred];
+    if layout.depth == 1 {
+        candidates.extend(
+            [
+                BlockHeight::One,
+                BlockHeight::Two,
+                BlockHeight::Four,
+                BlockHeight::Eight,
+                BlockHeight::Sixteen,
+                BlockHeight::ThirtyTwo,
+            ]
+            .into_iter()
+            .filter(|b| *b != inferred),
+        );
+    }
+
+    let width = div_round_up(layout.width, layout.block_dim.width.get());
+    let height = div_round_up(layout.height, layout.block_dim.height.get());
+    let row_size = width as usize * layout.bytes_per_pixel as usize;
+
+    let mut scores: Vec<_> = candidates
+        .into_iter()
+        .filter(|block_height| {
+            let layout = SurfaceLayout {
+                block_height_mip0: Some(*block_height),
+                ..layout
+            };
+            layout.swizzled_size().is_ok_and(|size| size <= data.len())
+        })
+        .map(|block_height| {
+            // Incorrect block heights scramble the rows within each block,
+            // so compare the bytes of adjacent rows of pixels or compressed blocks.
+            let mip_block_height = mip_block_height(height, block_height);
+            let mip = deswizzle_block_linear(
+                width,
+                height,
+                1,
+                data,
+                mip_block_height,
+                layout.bytes_per_pixel,
+            )
+            .unwrap_or_default();
+
+            let rows: Vec<_> = mip.chunks_exact(row_size).collect();
+            let difference: u64 = rows
+                .windows(2)
+                .map(|rows| {
+                    rows[0]
+                        .iter()
+                        .zip(rows[1])
+                        .map(|(a, b)| a.abs_diff(*b) as u64)
+                        .sum::<u64>()
+                })
+                .sum();
+            let count = rows.len().saturating_sub(1) * row_size;
+            let score = if count > 0 {
+                difference as f64 / count as f64
+            } else {
+                0.0
+            };
+
+            (block_height, score)
+        })
+        .collect();
+
+    scores.sort_by(|a, b| a.1.total_cmp(&b.1));
+    scores
+}
+
+fn check_source(source: &[u8], expected_size: usize) -> Result<(), SwizzleError> {
+    if source.len() < expected_size {
+        Err(SwizzleError::NotEnoughData {
+            expected_size,
+            actual_size: source.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Tiles or untiles `source` to `output`.
+/// The caller must check that both buffers have at least the swizzled or deswizzled size.
+fn swizzle_inner<const DESWIZZLE: bool>(layout: SurfaceLayout, source: &[u8], output: &mut [u8]) {
+    if layout.is_empty() {
+        return;
+    }
+
+    #[cfg(feature = "rayon")]
+    parallel::swizzle::<DESWIZZLE>(layout, source, output);
+
+    #[cfg(not(feature = "rayon"))]
+    for mip in layout.mips() {
+        let (source_range, output_range) = mip_ranges::<DESWIZZLE>(&mip);
+        swizzle_mip::<DESWIZZLE>(&mip, &source[source_range], &mut output[output_range]);
+    }
+}
+
+/// The ranges of `mip` in the source and output for tiling or untiling.
+fn mip_ranges<const DESWIZZLE: bool>(mip: &Mip) -> (Range<usize>, Range<usize>) {
+    (mip.data_range(DESWIZZLE), mip.data_range(!DESWIZZLE))
+}
+
+/// Tiles or untiles the data for a single mipmap.
+/// The `source` and `output` start at the offsets for `mip` in their respective layouts.
+fn swizzle_mip<const DESWIZZLE: bool>(mip: &Mip, source: &[u8], output: &mut [u8]) {
+    // Each 16 byte row within a GOB is contiguous, so copy up to 16 bytes at a time.
+    // Blocks are at most 16 bytes and evenly divide the GOB rows.
+    let bytes_per_pixel = mip.bytes_per_pixel as usize;
+    let row_size = mip.width as usize * bytes_per_pixel;
+    for z in 0..mip.depth {
+        for y in 0..mip.height {
+            let linear_row = mip.deswizzled_block_offset(0, y, z) - mip.deswizzled_offset;
+            for x in (0..row_size).step_by(16) {
+                let len = (row_size - x).min(16);
+                let linear = linear_row + x;
+                let tiled = mip.swizzled_block_offset((x / bytes_per_pixel) as u32, y, z)
+                    - mip.swizzled_offset;
+                if DESWIZZLE {
+                    output[linear..linear + len].copy_from_slice(&source[tiled..tiled + len]);
+                } else {
+                    output[tiled..tiled + len].copy_from_slice(&source[linear..linear + len]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::{mip_ranges, swizzle_mip, SurfaceLayout};
+
+    pub fn swizzle<const DESWIZZLE: bool>(layout: SurfaceLayout, source: &[u8], output: &mut [u8]) {
+        let mips = layout.mips();
+
+        // Split the output into disjoint regions to write each mipmap independently.
+        // Any remaining bytes are alignment padding between layers.
+        let mut regions = Vec::with_capacity(mips.len());
+        let mut remaining = output;
+        let mut position = 0;
+        for mip in &mips {
+            let (_, range) = mip_ranges::<DESWIZZLE>(mip);
+            let (_, rest) = std::mem::take(&mut remaining).split_at_mut(range.start - position);
+            let (region, rest) = rest.split_at_mut(range.len());
+            regions.push((mip, region));
+            remaining = rest;
+            position = range.end;
+        }
+
+        regions.into_par_iter().for_each(|(mip, region)| {
+            let (range, _) = mip_ranges::<DESWIZZLE>(mip);
+            swizzle_mip::<DESWIZZLE>(mip, &source[range], region);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    /// Counts allocations with the watched sizes to detect copies of an entire surface.
+    struct CountingAllocator;
+
+    static WATCHED_SIZES: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+    static WATCHED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn count_allocation(size: usize) {
+        if WATCHED_SIZES
+            .iter()
+            .any(|s| s.load(Ordering::Relaxed) == size)
+        {
+            WATCHED_COUNT.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count_allocation(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            count_allocation(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count_allocation(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn data(size: usize) -> Vec<u8> {
+        // Avoid repeating patterns that could hide incorrect offsets.
+        (0..size).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    fn check_swizzle(layout: SurfaceLayout) {
+        let deswizzled = data(layout.deswizzled_size().unwrap());
+        let expected_swizzled = tegra_swizzle::surface::swizzle_surface(
+            layout.width,
+            layout.height,
+            layout.depth,
+            &deswizzled,
+            layout.block_dim,
+            layout.block_height_mip0,
+            layout.bytes_per_pixel,
+            layout.mipmap_count,
+            layout.layer_count,
+        )
+        .unwrap();
+
+        let swizzled = swizzle_surface(layout, &deswizzled).unwrap();
+        assert!(swizzled == expected_swizzled, "swizzle {layout:?}");
+
+        let swizzled = data(layout.swizzled_size().unwrap());
+        let expected_deswizzled = tegra_swizzle::surface::deswizzle_surface(
+            layout.width,
+            layout.height,
+            layout.depth,
+            &swizzled,
+            layout.block_dim,
+            layout.block_height_mip0,
+            layout.bytes_per_pixel,
+            layout.mipmap_count,
+            layout.layer_count,
+        )
+        .unwrap();
+
+        let deswizzled = deswizzle_surface(layout, &swizzled).unwrap();
+        assert!(deswizzled == expected_deswizzled, "deswizzle {layout:?}");
+    }
+
+    fn layout(
+        width: u32,
+        height: u32,
+        block_dim: BlockDim,
+        bytes_per_pixel: u32,
+        mipmap_count: u32,
+        layer_count: u32,
+    ) -> SurfaceLayout {
+        SurfaceLayout {
+            width,
+            height,
+            depth: 1,
+            block_dim,
+            block_height_mip0: None,
+            bytes_per_pixel,
+            mipmap_count,
+            layer_count,
+        }
+    }
+
+    #[test]
+    fn swizzle_rgba8_cube_map() {
+        check_swizzle(layout(256, 256, BlockDim::uncompressed(), 4, 9, 6));
+    }
+
+    #[test]
+    fn swizzle_rgba8_odd_dimensions() {
+        check_swizzle(layout(67, 33, BlockDim::uncompressed(), 4, 7, 3));
+    }
+
+    #[test]
+    fn swizzle_r8_odd_dimensions() {
+        check_swizzle(layout(129, 31, BlockDim::uncompressed(), 1, 8, 1));
+    }
+
+    #[test]
+    fn swizzle_rgba32f_layers() {
+        check_swizzle(layout(40, 24, BlockDim::uncompressed(), 16, 6, 2));
+    }
+
+    #[test]
+    fn swizzle_bc1_odd_dimensions() {
+        check_swizzle(layout(100, 60, BlockDim::block_4x4(), 8, 7, 2));
+    }
+
+    #[test]
+    fn swizzle_bc7_cube_map() {
+        check_swizzle(layout(128, 128, BlockDim::block_4x4(), 16, 8, 6));
+    }
+
+    #[test]
+    fn swizzle_bc7_odd_dimensions() {
+        check_swizzle(layout(36, 20, BlockDim::block_4x4(), 16, 6, 3));
+    }
+
+    #[test]
+    fn swizzle_bc3_block_height() {
+        check_swizzle(SurfaceLayout {
+            block_height_mip0: Some(BlockHeight::Two),
+            ..layout(512, 256, BlockDim::block_4x4(), 16, 10, 2)
+        });
+    }
+
+    #[test]
+    fn swizzle_rgba8_3d() {
+        check_swizzle(SurfaceLayout {
+            depth: 16,
+            ..layout(16, 16, BlockDim::uncompressed(), 4, 5, 1)
+        });
+    }
+
+    #[test]
+    fn swizzle_bc1_3d_odd_dimensions() {
+        check_swizzle(SurfaceLayout {
+            depth: 5,
+            ..layout(36, 20, BlockDim::block_4x4(), 8, 3, 1)
+        });
+    }
+
+    #[test]
+    fn swizzle_into_without_surface_allocation() {
+        let layout = layout(113, 57, BlockDim::uncompressed(), 4, 1, 1);
+        let deswizzled_size = layout.deswizzled_size().unwrap();
+        let swizzled_size = layout.swizzled_size().unwrap();
+        let deswizzled = data(deswizzled_size);
+        let mut swizzled = Vec::with_capacity(swizzled_size);
+        let mut output = vec![0u8; deswizzled_size];
+
+        WATCHED_SIZES[0].store(deswizzled_size, Ordering::Relaxed);
+        WATCHED_SIZES[1].store(swizzled_size, Ordering::Relaxed);
+        swizzle_surface_into(layout, &deswizzled, &mut swizzled).unwrap();
+        deswizzle_surface_into(layout, &swizzled, &mut output).unwrap();
+        WATCHED_SIZES[0].store(0, Ordering::Relaxed);
+        WATCHED_SIZES[1].store(0, Ordering::Relaxed);
+
+        assert_eq!(0, WATCHED_COUNT.load(Ordering::Relaxed));
+        assert_eq!(deswizzled, output);
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt::Display;
+
+use crate::{
+    convert::{calculate_layer_mip_sizes, check_format, is_valid_surface, unk2},
+    LayerMipmaps, NutexbFile, TextureName, MAX_MIPMAP_COUNT,
+};
+
+/// How serious a [ValidationIssue] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file is unusual but can still be written and loaded.
+    Warning,
+    /// The file will likely fail to write correctly or fail to load in game.
+    Error,
+}
+
+/// A structural problem found by [NutexbFile::validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The name does not fit in the footer with its null terminator.
+    NameTooLong { length: usize },
+    /// The mipmap count is zero or does not fit in the mipmap sizes for each layer.
+    InvalidMipmapCount { mipmap_count: u32 },
+    /// The mipmap count is higher than the number of mipmaps down to 1x1 for the dimensions.
+    MipmapCountExceedsDimensions {
+        mipmap_count: u32,
+        max_mipmap_count: u32,
+    },
+    /// The width, height, or depth is zero.
+    ZeroDimensions { width: u32, height: u32, depth: u32 },
+    /// The base width or height of a compressed format is not a multiple of the block dimensions.
+    UnalignedDimensions {
+        width: u32,
+        height: u32,
+        block_width: u32,
+        block_height: u32,
+    },
+    /// The image format is not known, so image data sizes can't be checked.
+    UnknownFormat(u32),
+    /// The number of [LayerMipmaps] does not match the layer count in the footer.
+    LayerCountMismatch {
+        layer_count: u32,
+        layer_mipmaps_count: usize,
+    },
+    /// The mipmap sizes for a layer differ from the sizes calculated from the footer.
+    MipmapSizesMismatch {
+        layer: usize,
+        expected: Vec<u32>,
+        actual: Vec<u32>,
+    },
+    /// The data size in the footer does not match the length of the image data.
+    FooterDataSizeMismatch { data_size: u32, data_length: usize },
+    /// The length of the image data does not match the size calculated from the footer
+    /// using the swizzled or unswizzled layout.
+    UnexpectedDataLength { expected: usize, actual: usize },
+    /// The `unk2` value differs from the value used for this depth and layer count.
+    UnexpectedUnk2 { expected: u32, actual: u32 },
+}
+
+impl ValidationIssue {
+    /// How serious the issue is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValidationIssue::NameTooLong { .. }
+            | ValidationIssue::InvalidMipmapCount { .. }
+            | ValidationIssue::ZeroDimensions { .. }
+            | ValidationIssue::LayerCountMismatch { .. }
+            | ValidationIssue::FooterDataSizeMismatch { .. } => Severity::Error,
+            // Missing data can't be deswizzled, but padding is harmless.
+            ValidationIssue::UnexpectedDataLength { expected, actual } => {
+                if actual < expected {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                }
+            }
+            ValidationIssue::MipmapCountExceedsDimensions { .. }
+            | ValidationIssue::UnalignedDimensions { .. }
+            | ValidationIssue::UnknownFormat(_)
+            | ValidationIssue::MipmapSizesMismatch { .. }
+            | ValidationIssue::UnexpectedUnk2 { .. } => Severity::Warning,
+        }
+    }
+
+    /// Returns `true` if [NutexbFile::repair] fixes this issue.
+    pub fn is_repairable(&self) -> bool {
+        !matches!(
+            self,
+            ValidationIssue::ZeroDimensions { .. }
+                | ValidationIssue::UnalignedDimensions { .. }
+                | ValidationIssue::UnknownFormat(_)
+        )
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::NameTooLong { length } => write!(
+                f,
+                "name length {length} exceeds the maximum of {} bytes",
+                TextureName::MAX_LEN
+            ),
+            ValidationIssue::InvalidMipmapCount { mipmap_count } => write!(
+                f,
+                "mipmap count {mipmap_count} is not in the range 1 to {MAX_MIPMAP_COUNT}"
+            ),
+            ValidationIssue::MipmapCountExceedsDimensions {
+                mipmap_count,
+                max_mipmap_count,
+            } => write!(
+                f,
+                "mipmap count {mipmap_count} exceeds the maximum of {max_mipmap_count} for the dimensions"
+            ),
+            ValidationIssue::ZeroDimensions {
+                width,
+                height,
+                depth,
+            } => write!(f, "dimensions {width}x{height}x{depth} contain zero"),
+            ValidationIssue::UnalignedDimensions {
+                width,
+                height,
+                block_width,
+                block_height,
+            } => write!(
+                f,
+                "dimensions {width}x{height} are not a multiple of the {block_width}x{block_height} block size"
+            ),
+            ValidationIssue::UnknownFormat(value) => {
+                write!(f, "unknown image format 0x{value:X}")
+            }
+            ValidationIssue::LayerCountMismatch {
+                layer_count,
+                layer_mipmaps_count,
+            } => write!(
+                f,
+                "layer count {layer_count} does not match {layer_mipmaps_count} layer mipmaps"
+            ),
+            ValidationIssue::MipmapSizesMismatch {
+                layer,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "mipmap sizes {actual:?} for layer {layer} do not match the expected sizes {expected:?}"
+            ),
+            ValidationIssue::FooterDataSizeMismatch {
+                data_size,
+                data_length,
+            } => write!(
+                f,
+                "footer data size {data_size} does not match the data length {data_length}"
+            ),
+            ValidationIssue::UnexpectedDataLength { expected, actual } => write!(
+                f,
+                "data length {actual} does not match the expected length {expected}"
+            ),
+            ValidationIssue::UnexpectedUnk2 { expected, actual } => {
+                write!(f, "unk2 value {actual} does not match the expected value {expected}")
+            }
+        }
+    }
+}
+
+pub fn validate(nutexb: &NutexbFile) -> Vec<ValidationIssue> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    let mut issues = Vec::new();
+
+    let name_length = footer.name.as_bytes().len();
+    if name_length > TextureName::MAX_LEN {
+        issues.push(ValidationIssue::NameTooLong {
+            length: name_length,
+        });
+    }
+
+    if footer.width == 0 || footer.height == 0 || footer.depth == 0 {
+        issues.push(ValidationIssue::ZeroDimensions {
+            width: footer.width,
+            height: footer.height,
+            depth: footer.depth,
+        });
+    }
+
+    if footer.mipmap_count == 0 || footer.mipmap_count > MAX_MIPMAP_COUNT {
+        issues.push(ValidationIssue::InvalidMipmapCount {
+            mipmap_count: footer.mipmap_count,
+        });
+    } else {
+        let max_mipmap_count = max_mipmap_count(nutexb);
+        if footer.mipmap_count > max_mipmap_count {
+            issues.push(ValidationIssue::MipmapCountExceedsDimensions {
+                mipmap_count: footer.mipmap_count,
+                max_mipmap_count,
+            });
+        }
+    }
+
+    if let crate::NutexbFormat::Unknown(value) = format {
+        issues.push(ValidationIssue::UnknownFormat(value));
+    } else if !footer.width.is_multiple_of(format.block_width())
+        || !footer.height.is_multiple_of(format.block_height())
+    {
+        issues.push(ValidationIssue::UnalignedDimensions {
+            width: footer.width,
+            height: footer.height,
+            block_width: format.block_width(),
+            block_height: format.block_height(),
+        });
+    }
+
+    if nutexb.layer_mipmaps.len() != footer.layer_count as usize {
+        issues.push(ValidationIssue::LayerCountMismatch {
+            layer_count: footer.layer_count,
+            layer_mipmaps_count: nutexb.layer_mipmaps.len(),
+        });
+    }
+
+    if let Some(expected) = expected_layer_mipmaps(nutexb) {
+        for (layer, (expected, actual)) in expected.iter().zip(&nutexb.layer_mipmaps).enumerate() {
+            if expected.mipmap_sizes != actual.mipmap_sizes {
+                issues.push(ValidationIssue::MipmapSizesMismatch {
+                    layer,
+                    expected: expected.mipmap_sizes.clone(),
+                    actual: actual.mipmap_sizes.clone(),
+                });
+            }
+        }
+    }
+
+    if footer.data_size as usize != nutexb.data.len() {
+        issues.push(ValidationIssue::FooterDataSizeMismatch {
+            data_size: footer.data_size,
+            data_length: nutexb.data.len(),
+        });
+    }
+
+    if let Some(expected) = nutexb.expected_data_size() {
+        if expected != nutexb.data.len() {
+            issues.push(ValidationIssue::UnexpectedDataLength {
+                expected,
+                actual: nutexb.data.len(),
+            });
+        }
+    }
+
+    let expected_unk2 = expected_unk2(nutexb);
+    if footer.unk2 != expected_unk2 {
+        issues.push(ValidationIssue::UnexpectedUnk2 {
+            expected: expected_unk2,
+            actual: footer.unk2,
+        });
+    }
+
+    issues
+}
+
+pub fn repair(nutexb: &mut NutexbFile) -> Vec<ValidationIssue> {
+    if nutexb.footer.name.as_bytes().len() > TextureName::MAX_LEN {
+        nutexb.footer.name = TextureName::truncated(nutexb.footer.name.as_str());
+    }
+
+    // Changing the mipmap count also changes the expected sizes below.
+    nutexb.footer.mipmap_count = nutexb
+        .footer
+        .mipmap_count
+        .clamp(1, max_mipmap_count(nutexb).min(MAX_MIPMAP_COUNT));
+
+    nutexb.footer.unk2 = expected_unk2(nutexb);
+
+    nutexb.layer_mipmaps = expected_layer_mipmaps(nutexb).unwrap_or_else(|| {
+        // Preserve the existing sizes when the format isn't known.
+        let mut layer_mipmaps = nutexb.layer_mipmaps.clone();
+        layer_mipmaps.resize(
+            nutexb.footer.layer_count as usize,
+            LayerMipmaps {
+                mipmap_sizes: Vec::new(),
+                padding: Vec::new(),
+            },
+        );
+        for layer in &mut layer_mipmaps {
+            layer
+                .mipmap_sizes
+                .resize(nutexb.footer.mipmap_count as usize, 0);
+        }
+        layer_mipmaps
+    });
+
+    nutexb.optimize_size();
+    nutexb.footer.data_size = nutexb.data.len() as u32;
+
+    validate(nutexb)
+}
+
+pub(crate) fn max_mipmap_count(nutexb: &NutexbFile) -> u32 {
+    // Each mipmap halves the dimensions down to a minimum of 1x1.
+    let footer = &nutexb.footer;
+    let max_dimension = footer.width.max(footer.height).max(footer.depth).max(1);
+    u32::BITS - max_dimension.leading_zeros()
+}
+
+fn expected_unk2(nutexb: &NutexbFile) -> u32 {
+    if nutexb.footer.unk3 == 0x1000 {
+        unk2(nutexb.footer.depth, nutexb.footer.layer_count)
+    } else {
+        2
+    }
+}
+
+fn expected_layer_mipmaps(nutexb: &NutexbFile) -> Option<Vec<LayerMipmaps>> {
+    let footer = &nutexb.footer;
+    let format = footer.image_format;
+    if check_format(format).is_err()
+        || footer.mipmap_count > MAX_MIPMAP_COUNT
+        || !is_valid_surface(
+            footer.width,
+            footer.height,
+            footer.depth,
+            format.bytes_per_pixel(),
+            footer.mipmap_count,
+        )
+    {
+        return None;
+    }
+
+    Some(calculate_layer_mip_sizes(
+        footer.width,
+        footer.height,
+        footer.depth,
+        format.block_dim(),
+        format.bytes_per_pixel(),
+        footer.mipmap_count,
+        footer.layer_count,
+    ))
+}
@@ -1,13 +1,18 @@
 use std::{
     fs::File,
+    io::Cursor,
     path::{Path, PathBuf},
 };
 
-use nutexb::NutexbFile;
+use nutexb::{NutexbFile, WriteFileOptions};
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let input_path = Path::new(&args[1]);
+    // Keep the previous output file as "<output>.bak" when passing --backup.
+    let (flags, args): (Vec<String>, Vec<String>) =
+        std::env::args().skip(1).partition(|a| a.starts_with("--"));
+    let keep_backup = flags.iter().any(|f| f == "--backup");
+
+    let input_path = Path::new(&args[0]);
 
     // Infer the extension to allow drag and drop support.
     let new_extension = match input_path.extension().unwrap().to_str().unwrap() {
@@ -15,13 +20,16 @@ fn main() {
         _ => "nutexb",
     };
     let output_path = args
-        .get(2)
+        .get(1)
         .map(PathBuf::from)
         .unwrap_or_else(|| input_path.with_extension(new_extension));
 
     let output_name = output_path.file_name().unwrap().to_str().unwrap();
-    let output_file = File::create(&output_path).unwrap();
-    let mut output_file = std::io::BufWriter::new(output_file);
+
+    let options = WriteFileOptions {
+        keep_backup,
+        ..Default::default()
+    };
 
     let start = std::time::Instant::now();
     match input_path.extension().unwrap().to_str().unwrap() {
@@ -29,17 +37,23 @@ fn main() {
             let mut reader = File::open(input_path).unwrap();
             let dds = nutexb::ddsfile::Dds::read(&mut reader).unwrap();
             let nutexb = NutexbFile::from_dds(&dds, output_name).unwrap();
-            nutexb.write_to_file(output_path).unwrap();
+            nutexb
+                .write_to_file_with_options(&output_path, options)
+                .unwrap();
         }
         "nutexb" => {
             let nutexb = nutexb::NutexbFile::read_from_file(input_path).unwrap();
             let dds = nutexb.to_dds().unwrap();
-            dds.write(&mut output_file).unwrap();
+            let mut writer = Cursor::new(Vec::new());
+            dds.write(&mut writer).unwrap();
+            nutexb::write_file_atomic(&output_path, writer.get_ref(), keep_backup).unwrap();
         }
         _ => {
             let image = nutexb::image::open(input_path).unwrap();
             let nutexb = NutexbFile::from_image(&image.to_rgba8(), output_name).unwrap();
-            nutexb.write_to_file(output_path).unwrap();
+            nutexb
+                .write_to_file_with_options(&output_path, options)
+                .unwrap();
         }
     }
     println!("Completed operation in {:?}", start.elapsed());
@@ -0,0 +1,83 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use nutexb::{NutexbFile, NutexbFormat, Surface};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nutexb_cli_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+fn write_nutexb(path: &Path) {
+    let surface = Surface {
+        width: 4,
+        height: 4,
+        depth: 1,
+        image_data: vec![255u8; 4 * 4 * 4],
+        mipmap_count: 1,
+        layer_count: 1,
+        image_format: NutexbFormat::R8G8B8A8Unorm,
+    };
+    NutexbFile::from_surface(surface, "tex")
+        .unwrap()
+        .write_to_file(path)
+        .unwrap();
+}
+
+#[test]
+fn nutexb_to_dds_with_backup() {
+    let dir = test_dir("backup");
+    let input = dir.join("tex.nutexb");
+    let output = dir.join("tex.dds");
+    write_nutexb(&input);
+    std::fs::write(&output, b"old").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_nutexb_cli"))
+        .arg(&input)
+        .arg("--backup")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(
+        vec!["tex.dds", "tex.dds.bak", "tex.nutexb"],
+        file_names(&dir)
+    );
+    assert_eq!(
+        b"old",
+        std::fs::read(dir.join("tex.dds.bak")).unwrap().as_slice()
+    );
+    assert!(std::fs::read(&output).unwrap().starts_with(b"DDS "));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn nutexb_to_dds_without_backup() {
+    let dir = test_dir("no_backup");
+    let input = dir.join("tex.nutexb");
+    write_nutexb(&input);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_nutexb_cli"))
+        .arg(&input)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(vec!["tex.dds", "tex.nutexb"], file_names(&dir));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}